@@ -0,0 +1,869 @@
+//! `#[derive(Schema)]`: generates the SurrealQL `DEFINE TABLE` / `DEFINE
+//! FIELD` statements for a Rust type. The trait and the impls for leaf types
+//! live in `website::surql`; this crate only writes the impls for structs
+//! and enums.
+//!
+//! - A struct is an `object`; each named field becomes `DEFINE FIELD
+//!   <path>.<field>`, recursing into the field's own type.
+//! - An enum is externally tagged, like serde's default: unit variants are
+//!   strings, data variants are `{ "<Variant>": ... }` objects, so each data
+//!   variant becomes an optional `<path>.<Variant>` field.
+//! - `#[schema(table = "posts")]` also makes the type a table: it gets a
+//!   `TABLE` constant and `DEFINE TABLE posts SCHEMAFULL`.
+//! - Every field is also asked for its `Reference<T>` paths, so a table
+//!   knows what to `FETCH`.
+//! - Fields named `id` are SurrealDB's record id and are never defined.
+//!   `#[schema(skip)]` or `#[serde(skip)]` leaves out any other field;
+//!   `#[serde(rename = "...")]` is honoured, and so is the container's
+//!   `#[serde(rename_all = "...")]` (a struct variant's fields follow the
+//!   variant's own `rename_all`, else the enum's `rename_all_fields`).
+//!
+//! `#[derive(Fields)]`: the editor's definition of each field of a struct,
+//! from `#[field(label = "...", hint = "...", form_type = InputArea)]`.
+//! The label defaults to the field name (its `rename`, else its ident) in
+//! sentence case. The form type defaults to one for the field's Rust type
+//! (`bool` is `Boolean`, numbers `Number`, `DateTime` `DateTime`, `Url`
+//! `Url`, `Reference<T>` a reference to `T`'s table, `Vec<T>` an `Array`
+//! of `T`'s, any other type an `Object` of its own `Fields`, `String`
+//! `InputText`), looking through `Option`, which also makes the field
+//! optional. `id`, skipped and `#[field(skip)]` fields are left out.
+//! The field's `#[validate(...)]` rules are part of its definition.
+//!
+//! `#[derive(Validate)]`: checks each field against its `#[validate(...)]`
+//! rules, the same attribute `schemars` reads for the JSON Schema:
+//! `required`, `length(min = 1, max = 200)` (characters or items),
+//! `regex(pattern = "^...$")`, `url` and `date_range(min = "<RFC 3339>",
+//! max = "...")`; an invalid `pattern` is a compile error. `nested` also
+//! validates the field's own fields. On an enum, each newtype variant's
+//! value is validated under the variant's name.
+//!
+//! `#[derive(Widgets)]`: the widget each field picks with `#[widget(...)]`,
+//! by name, with string options: `#[widget(markdown)]`,
+//! `#[widget(reference(table = "authors"))]`. The widgets themselves are
+//! registered at runtime, in `website::widgets`. `#[visible_if(og_type ==
+//! Article)]` shows a field only while a sibling field has that value (a
+//! variant name or a string literal; a string for a renamed variant).
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, FieldsNamed, Generics,
+    LitStr,
+};
+
+#[proc_macro_derive(Schema, attributes(schema))]
+pub fn derive_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let table = container_table(&input.attrs)?;
+    let rename_all = RenameAll::parse(&input.attrs, "rename_all")?;
+    let mut has_id = false;
+    let (surql_type, Walk { defines, references }) = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                has_id = fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == "id"));
+                (quote!(::std::string::String::from("object")), struct_fields(fields, rename_all)?)
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Schema can only be derived for structs with named fields",
+                ));
+            }
+        },
+        Data::Enum(data) => {
+            enum_fields(data, rename_all, RenameAll::parse(&input.attrs, "rename_all_fields")?)?
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(&input.ident, "Schema cannot be derived for unions"));
+        }
+    };
+
+    let ident = &input.ident;
+    let generics = add_schema_bounds(input.generics.clone());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut tokens = quote! {
+        impl #impl_generics ::website::surql::Schema for #ident #ty_generics #where_clause {
+            fn surql_type() -> ::std::string::String {
+                #surql_type
+            }
+
+            #[allow(unused_variables)]
+            fn define_fields(
+                table: &str,
+                path: &str,
+                out: &mut ::std::vec::Vec<::std::string::String>,
+            ) {
+                #defines
+            }
+
+            #[allow(unused_variables)]
+            fn references(path: &str, out: &mut ::std::vec::Vec<::std::string::String>) {
+                #references
+            }
+        }
+    };
+
+    if let Some(table) = table {
+        let record_id = if has_id {
+            quote!(::website::surql::RecordId::record_id(&self.id))
+        } else {
+            quote!(::std::option::Option::None)
+        };
+        tokens.extend(quote! {
+            impl #impl_generics ::website::surql::TableSchema for #ident #ty_generics #where_clause {
+                const TABLE: &'static str = #table;
+
+                fn record_id(&self) -> ::std::option::Option<&::website::surql::Thing> {
+                    #record_id
+                }
+            }
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                pub const TABLE: &'static str = #table;
+            }
+        });
+    }
+    Ok(tokens)
+}
+
+#[proc_macro_derive(Fields, attributes(field, validate))]
+pub fn derive_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_fields(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_fields(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(syn::DataStruct { fields: Fields::Named(fields), .. }) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Fields can only be derived for structs with named fields",
+        ));
+    };
+
+    let rename_all = RenameAll::parse(&input.attrs, "rename_all")?;
+    let mut defs = TokenStream2::new();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named field");
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if ident == "id" || attrs.skip || is_skipped(&field.attrs)? {
+            continue;
+        }
+        let name = field_name(field, rename_all)?;
+        // A `rename_all` name is still labelled from the Rust ident: "Og title", not "OgTitle".
+        let label = match attrs.label {
+            Some(label) => label.value(),
+            None => sentence_case(&serde_name(&field.attrs)?.unwrap_or_else(|| ident.to_string())),
+        };
+        let hint = attrs.hint.map(|h| h.value()).unwrap_or_default();
+        let form_type = attrs.form_type.map_or_else(|| infer_form_type(&field.ty), |f| quote!(#f));
+        let optional = option_inner(&field.ty).is_some();
+        let rules = ValidateAttrs::parse(&field.attrs)?.rules;
+        defs.extend(quote! {
+            ::website::fields::FieldDef {
+                name: #name,
+                label: #label,
+                hint: #hint,
+                form_type: {
+                    #[allow(unused_imports)]
+                    use ::website::fields::FormType::*;
+                    #form_type
+                },
+                optional: #optional,
+                rules: &[#(#rules),*],
+            },
+        });
+    }
+
+    let ident = &input.ident;
+    let type_name = ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::website::fields::Fields for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #type_name;
+
+            fn fields() -> ::std::vec::Vec<::website::fields::FieldDef> {
+                ::std::vec![#defs]
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_validate(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_validate(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let rename_all = RenameAll::parse(&input.attrs, "rename_all")?;
+    let body = match &input.data {
+        Data::Struct(syn::DataStruct { fields: Fields::Named(fields), .. }) => {
+            let mut checks = TokenStream2::new();
+            for field in &fields.named {
+                let ident = field.ident.as_ref().expect("named field");
+                if ident == "id" || is_skipped(&field.attrs)? {
+                    continue;
+                }
+                let ValidateAttrs { rules, nested } = ValidateAttrs::parse(&field.attrs)?;
+                if rules.is_empty() && !nested {
+                    continue;
+                }
+                let name = field_name(field, rename_all)?;
+                let check = (!rules.is_empty()).then(|| {
+                    quote!(::website::validation::check(&self.#ident, &[#(#rules),*], &path, errors);)
+                });
+                let nested = nested.then(|| {
+                    quote!(::website::validation::Validate::validate_into(&self.#ident, &path, errors);)
+                });
+                checks.extend(quote! {
+                    {
+                        let path = ::website::surql::join(path, #name);
+                        #check
+                        #nested
+                    }
+                });
+            }
+            checks
+        }
+        Data::Enum(data) => {
+            let mut arms = TokenStream2::new();
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let name = variant_name(variant, rename_all)?;
+                arms.extend(match &variant.fields {
+                    Fields::Unit => quote!(Self::#ident => {}),
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote! {
+                        Self::#ident(value) => ::website::validation::Validate::validate_into(
+                            value,
+                            &::website::surql::join(path, #name),
+                            errors,
+                        ),
+                    },
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            variant,
+                            "Validate supports unit and newtype variants",
+                        ));
+                    }
+                });
+            }
+            quote!(match self { #arms })
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Validate can only be derived for structs with named fields and enums",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::website::validation::Validate for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn validate_into(
+                &self,
+                path: &str,
+                errors: &mut ::std::vec::Vec<::website::validation::FieldError>,
+            ) {
+                #body
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(Widgets, attributes(widget, visible_if))]
+pub fn derive_widgets(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_widgets(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_widgets(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(syn::DataStruct { fields: Fields::Named(fields), .. }) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Widgets can only be derived for structs with named fields",
+        ));
+    };
+
+    let rename_all = RenameAll::parse(&input.attrs, "rename_all")?;
+    let mut uses = TokenStream2::new();
+    let mut conditions = TokenStream2::new();
+    for field in &fields.named {
+        let name = field_name(field, rename_all)?;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("visible_if")) {
+            let (when, equals) = visible_if_attr(attr)?;
+            // An unknown sibling is reported when the widgets are registered.
+            let when = match fields.named.iter().find(|f| f.ident.as_ref().is_some_and(|i| *i == when)) {
+                Some(sibling) => field_name(sibling, rename_all)?,
+                None => when,
+            };
+            conditions.extend(quote! {
+                ::website::widgets::FieldCondition {
+                    field: #name,
+                    when: #when,
+                    equals: #equals,
+                },
+            });
+        }
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("widget")) {
+            let (widget, options) = widget_attr(attr)?;
+            let options = options.iter().map(|(key, value)| quote!((#key, #value)));
+            uses.extend(quote! {
+                ::website::widgets::FieldWidget {
+                    field: #name,
+                    widget: #widget,
+                    options: &[#(#options),*],
+                },
+            });
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::website::widgets::Widgets for #ident #ty_generics #where_clause {
+            fn widgets() -> ::std::vec::Vec<::website::widgets::FieldWidget> {
+                ::std::vec![#uses]
+            }
+
+            fn conditions() -> ::std::vec::Vec<::website::widgets::FieldCondition> {
+                ::std::vec![#conditions]
+            }
+        }
+    })
+}
+
+/// `#[visible_if(field == Variant)]` or `#[visible_if(field == "value")]`:
+/// the sibling field and the value it must have.
+fn visible_if_attr(attr: &Attribute) -> syn::Result<(String, String)> {
+    let expr: syn::Expr = attr.parse_args()?;
+    let error = || syn::Error::new_spanned(&expr, "expected `field == Variant` or `field == \"value\"`");
+    let syn::Expr::Binary(binary) = &expr else {
+        return Err(error());
+    };
+    let (syn::BinOp::Eq(_), syn::Expr::Path(when)) = (&binary.op, &*binary.left) else {
+        return Err(error());
+    };
+    let when = when.path.get_ident().ok_or_else(error)?.to_string();
+    let equals = match &*binary.right {
+        syn::Expr::Path(value) => value.path.get_ident().ok_or_else(error)?.to_string(),
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) => value.value(),
+        _ => return Err(error()),
+    };
+    Ok((when, equals))
+}
+
+/// `#[widget(name)]` or `#[widget(name(key = "value", ...))]`.
+fn widget_attr(attr: &Attribute) -> syn::Result<(String, Vec<(String, LitStr)>)> {
+    let mut widget = None;
+    let mut options = Vec::new();
+    attr.parse_nested_meta(|meta| {
+        if widget.is_some() {
+            return Err(meta.error("a field has one widget"));
+        }
+        let name = meta.path.get_ident().ok_or_else(|| meta.error("expected a widget name"))?;
+        widget = Some(name.to_string());
+        if meta.input.peek(syn::token::Paren) {
+            meta.parse_nested_meta(|option| {
+                let key = option.path.get_ident().ok_or_else(|| option.error("expected `key = \"value\"`"))?;
+                options.push((key.to_string(), option.value()?.parse::<LitStr>()?));
+                Ok(())
+            })?;
+        }
+        Ok(())
+    })?;
+    let widget = widget.ok_or_else(|| syn::Error::new_spanned(attr, "expected `#[widget(name)]`"))?;
+    Ok((widget, options))
+}
+
+/// `#[validate(...)]` on a struct field: its rules, as `Rule` expressions.
+#[derive(Default)]
+struct ValidateAttrs {
+    rules: Vec<TokenStream2>,
+    nested: bool,
+}
+
+impl ValidateAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut out = ValidateAttrs::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("validate")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("required") {
+                    out.rules.push(quote!(::website::validation::Rule::Required));
+                } else if meta.path.is_ident("url") {
+                    out.rules.push(quote!(::website::validation::Rule::Url));
+                } else if meta.path.is_ident("nested") {
+                    out.nested = true;
+                } else if meta.path.is_ident("length") {
+                    let (mut min, mut max) = (None, None);
+                    meta.parse_nested_meta(|item| {
+                        let value: syn::LitInt = item.value()?.parse()?;
+                        if item.path.is_ident("min") {
+                            min = Some(value);
+                        } else if item.path.is_ident("max") {
+                            max = Some(value);
+                        } else if item.path.is_ident("equal") {
+                            min = Some(value.clone());
+                            max = Some(value);
+                        } else {
+                            return Err(item.error("expected `min`, `max` or `equal`"));
+                        }
+                        Ok(())
+                    })?;
+                    let (min, max) = (option_tokens(min), option_tokens(max));
+                    out.rules.push(quote!(::website::validation::Rule::Length { min: #min, max: #max }));
+                } else if meta.path.is_ident("regex") {
+                    let mut pattern = None;
+                    meta.parse_nested_meta(|item| {
+                        if item.path.is_ident("pattern") {
+                            pattern = Some(item.value()?.parse::<LitStr>()?);
+                            Ok(())
+                        } else {
+                            Err(item.error("expected `pattern = \"...\"`"))
+                        }
+                    })?;
+                    let pattern = pattern.ok_or_else(|| meta.error("expected `regex(pattern = \"...\")`"))?;
+                    if let Err(e) = regex::Regex::new(&pattern.value()) {
+                        return Err(syn::Error::new_spanned(&pattern, format!("invalid pattern: {}", e)));
+                    }
+                    out.rules.push(quote!(::website::validation::Rule::Pattern { pattern: #pattern }));
+                } else if meta.path.is_ident("date_range") {
+                    let (mut min, mut max) = (None, None);
+                    meta.parse_nested_meta(|item| {
+                        let value: LitStr = item.value()?.parse()?;
+                        if item.path.is_ident("min") {
+                            min = Some(value);
+                        } else if item.path.is_ident("max") {
+                            max = Some(value);
+                        } else {
+                            return Err(item.error("expected `min` or `max`"));
+                        }
+                        Ok(())
+                    })?;
+                    let (min, max) = (option_tokens(min), option_tokens(max));
+                    out.rules.push(quote!(::website::validation::Rule::DateRange { min: #min, max: #max }));
+                } else {
+                    return Err(meta.error(
+                        "expected `required`, `length`, `regex`, `url`, `date_range` or `nested`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+fn option_tokens<T: quote::ToTokens>(value: Option<T>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(::std::option::Option::Some(#value)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+/// `#[field(...)]` on a struct field.
+#[derive(Default)]
+struct FieldAttrs {
+    label: Option<LitStr>,
+    hint: Option<LitStr>,
+    form_type: Option<syn::Expr>,
+    skip: bool,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut out = FieldAttrs::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("field")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("label") {
+                    out.label = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("hint") {
+                    out.hint = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("form_type") {
+                    out.form_type = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip") {
+                    out.skip = true;
+                } else {
+                    return Err(meta.error("expected `label`, `hint`, `form_type` or `skip`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+/// The form type for a field of type `ty`, as an expression in scope of
+/// `FormType::*`.
+fn infer_form_type(ty: &syn::Type) -> TokenStream2 {
+    if let Some(inner) = option_inner(ty) {
+        return infer_form_type(inner);
+    }
+    let syn::Type::Path(path) = ty else {
+        return quote!(InputText);
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return quote!(InputText);
+    };
+    let argument = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    };
+    match (segment.ident.to_string().as_str(), argument) {
+        ("String" | "str", _) => quote!(InputText),
+        ("bool", _) => quote!(Boolean),
+        (
+            "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" | "f32" | "f64",
+            _,
+        ) => quote!(Number),
+        ("DateTime", _) => quote!(DateTime),
+        ("NaiveDate", _) => quote!(InputDate),
+        ("Url", _) => quote!(Url),
+        ("Reference", Some(target)) => quote!(Reference(<#target as ::website::surql::TableSchema>::TABLE)),
+        ("Vec", Some(item)) => {
+            let item = infer_form_type(item);
+            quote!(Array(&#item))
+        }
+        _ => quote!(Object(<#ty as ::website::fields::Fields>::NAME)),
+    }
+}
+
+/// `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `thumbnail_url` -> `Thumbnail url`.
+fn sentence_case(name: &str) -> String {
+    let words = name.replace('_', " ");
+    let mut chars = words.chars();
+    chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+}
+
+/// Generated statements for the fields of a struct or the variants of an
+/// enum: the body of `define_fields` and of `references`.
+#[derive(Default)]
+struct Walk {
+    defines: TokenStream2,
+    references: TokenStream2,
+}
+
+fn struct_fields(fields: &FieldsNamed, rename_all: RenameAll) -> syn::Result<Walk> {
+    let mut walk = Walk::default();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named field");
+        if ident == "id" || is_skipped(&field.attrs)? {
+            continue;
+        }
+        let name = field_name(field, rename_all)?;
+        let ty = &field.ty;
+        walk.defines.extend(quote! {
+            ::website::surql::define_field::<#ty>(table, path, #name, out);
+        });
+        walk.references.extend(quote! {
+            ::website::surql::field_references::<#ty>(path, #name, out);
+        });
+    }
+    Ok(walk)
+}
+
+/// Returns the enum's SurrealQL type and the statements for its variants.
+fn enum_fields(
+    data: &syn::DataEnum,
+    rename_all: RenameAll,
+    rename_all_fields: RenameAll,
+) -> syn::Result<(TokenStream2, Walk)> {
+    let mut has_unit = false;
+    let mut has_data = false;
+    let mut walk = Walk::default();
+
+    for variant in &data.variants {
+        if is_skipped(&variant.attrs)? {
+            continue;
+        }
+        let name = variant_name(variant, rename_all)?;
+        match &variant.fields {
+            Fields::Unit => has_unit = true,
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                has_data = true;
+                let ty = &fields.unnamed[0].ty;
+                walk.defines.extend(quote! {
+                    ::website::surql::define_variant::<#ty>(table, path, #name, out);
+                });
+                walk.references.extend(quote! {
+                    ::website::surql::field_references::<#ty>(path, #name, out);
+                });
+            }
+            Fields::Named(fields) => {
+                has_data = true;
+                let fields_rename = RenameAll::parse(&variant.attrs, "rename_all")?;
+                let fields_rename = if fields_rename == RenameAll::None { rename_all_fields } else { fields_rename };
+                let Walk { defines, references } = struct_fields(fields, fields_rename)?;
+                walk.defines.extend(quote! {
+                    {
+                        let path = &::website::surql::define_variant_object(table, path, #name, out);
+                        #defines
+                    }
+                });
+                walk.references.extend(quote! {
+                    {
+                        let path = &::website::surql::join(path, #name);
+                        #references
+                    }
+                });
+            }
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "Schema supports unit, newtype and struct variants, not tuple variants",
+                ));
+            }
+        }
+    }
+
+    let surql_type = match (has_unit, has_data) {
+        (true, true) => "string | object",
+        (false, true) => "object",
+        _ => "string",
+    };
+    Ok((quote!(::std::string::String::from(#surql_type)), walk))
+}
+
+fn add_schema_bounds(mut generics: Generics) -> Generics {
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::website::surql::Schema));
+    }
+    generics
+}
+
+/// `#[schema(table = "...")]` on the type.
+fn container_table(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut table = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("schema")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `table = \"...\"`"))
+            }
+        })?;
+    }
+    Ok(table)
+}
+
+/// `#[schema(skip)]`, `#[serde(skip)]` or `#[serde(skip_serializing)]`.
+fn is_skipped(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut skip = false;
+    for attr in attrs {
+        if attr.path().is_ident("schema") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `skip`"))
+                }
+            })?;
+        } else if attr.path().is_ident("serde") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                    skip = true;
+                }
+                skip_value(&meta)
+            })?;
+        }
+    }
+    Ok(skip)
+}
+
+/// `#[serde(rename = "...")]`, if any.
+fn serde_name(attrs: &[Attribute]) -> syn::Result<Option<String>> {
+    let mut name = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") && meta.input.peek(syn::Token![=]) {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                skip_value(&meta)
+            }
+        })?;
+    }
+    Ok(name)
+}
+
+/// A field's name in the stored JSON: its `rename`, else its ident under
+/// the container's `rename_all`.
+fn field_name(field: &syn::Field, rename_all: RenameAll) -> syn::Result<String> {
+    let ident = field.ident.as_ref().expect("named field").to_string();
+    Ok(serde_name(&field.attrs)?.unwrap_or_else(|| rename_all.field(&ident)))
+}
+
+/// A variant's tag: its `rename`, else its ident under the enum's
+/// `rename_all`.
+fn variant_name(variant: &syn::Variant, rename_all: RenameAll) -> syn::Result<String> {
+    Ok(serde_name(&variant.attrs)?.unwrap_or_else(|| rename_all.variant(&variant.ident.to_string())))
+}
+
+/// serde's `rename_all` rules. Fields are written in `snake_case` and
+/// variants in `PascalCase`, so each rule converts from one or the other,
+/// exactly as serde does.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RenameAll {
+    None,
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameAll {
+    const RULES: [(&'static str, Self); 8] = [
+        ("lowercase", Self::Lower),
+        ("UPPERCASE", Self::Upper),
+        ("PascalCase", Self::Pascal),
+        ("camelCase", Self::Camel),
+        ("snake_case", Self::Snake),
+        ("SCREAMING_SNAKE_CASE", Self::ScreamingSnake),
+        ("kebab-case", Self::Kebab),
+        ("SCREAMING-KEBAB-CASE", Self::ScreamingKebab),
+    ];
+
+    /// `#[serde(<option> = "...")]`, or `<option>(serialize = "...",
+    /// deserialize = "...")` with the same rule both ways: the form and the
+    /// table have one name per field.
+    fn parse(attrs: &[Attribute], option: &str) -> syn::Result<Self> {
+        let mut rule = Self::None;
+        for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident(option) {
+                    return skip_value(&meta);
+                }
+                if meta.input.peek(syn::Token![=]) {
+                    rule = Self::from_lit(&meta.value()?.parse()?)?;
+                    return Ok(());
+                }
+                let (mut serialize, mut deserialize) = (None, None);
+                meta.parse_nested_meta(|inner| {
+                    let side = if inner.path.is_ident("serialize") {
+                        &mut serialize
+                    } else if inner.path.is_ident("deserialize") {
+                        &mut deserialize
+                    } else {
+                        return Err(inner.error("expected `serialize` or `deserialize`"));
+                    };
+                    *side = Some(Self::from_lit(&inner.value()?.parse()?)?);
+                    Ok(())
+                })?;
+                match (serialize, deserialize) {
+                    (Some(serialize), Some(deserialize)) if serialize == deserialize => {
+                        rule = serialize;
+                        Ok(())
+                    }
+                    _ => Err(meta.error(format!(
+                        "the derives need one name per field; use `{option} = \"...\"`"
+                    ))),
+                }
+            })?;
+        }
+        Ok(rule)
+    }
+
+    fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        let value = lit.value();
+        Self::RULES.iter().find(|(name, _)| *name == value).map(|(_, rule)| *rule).ok_or_else(|| {
+            let names: Vec<_> = Self::RULES.iter().map(|(name, _)| format!("\"{name}\"")).collect();
+            syn::Error::new_spanned(lit, format!("unknown rename rule; expected one of {}", names.join(", ")))
+        })
+    }
+
+    /// A `snake_case` field name under this rule.
+    fn field(self, field: &str) -> String {
+        match self {
+            Self::None | Self::Lower | Self::Snake => field.to_string(),
+            Self::Upper | Self::ScreamingSnake => field.to_ascii_uppercase(),
+            Self::Pascal => field
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map_or_else(String::new, |c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                })
+                .collect(),
+            Self::Camel => {
+                let pascal = Self::Pascal.field(field);
+                let mut chars = pascal.chars();
+                chars.next().map_or_else(String::new, |c| c.to_ascii_lowercase().to_string() + chars.as_str())
+            }
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => Self::ScreamingSnake.field(field).replace('_', "-"),
+        }
+    }
+
+    /// A `PascalCase` variant name under this rule.
+    fn variant(self, variant: &str) -> String {
+        match self {
+            Self::None | Self::Pascal => variant.to_string(),
+            Self::Lower => variant.to_ascii_lowercase(),
+            Self::Upper => variant.to_ascii_uppercase(),
+            Self::Camel => {
+                let mut chars = variant.chars();
+                chars.next().map_or_else(String::new, |c| c.to_ascii_lowercase().to_string() + chars.as_str())
+            }
+            Self::Snake => {
+                let mut snake = String::new();
+                for (i, c) in variant.char_indices() {
+                    if i > 0 && c.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(c.to_ascii_lowercase());
+                }
+                snake
+            }
+            Self::ScreamingSnake => Self::Snake.variant(variant).to_ascii_uppercase(),
+            Self::Kebab => Self::Snake.variant(variant).replace('_', "-"),
+            Self::ScreamingKebab => Self::ScreamingSnake.variant(variant).replace('_', "-"),
+        }
+    }
+}
+
+/// Consumes the rest of a serde option this macro does not care about, e.g.
+/// `default = "..."` or `with(...)`.
+fn skip_value(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        content.parse::<TokenStream2>()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::services::ServeDir;
+use tower_http::trace::TraceLayer;
+
+use crate::telemetry::{self, REQUEST_ID_HEADER};
+use crate::{dev, error, handlers, AppState};
+
+/// Every route, the static-file fallback and the middleware, bound to
+/// `state`. `main` serves it; tests drive it in-process.
+pub fn app(state: Arc<AppState>) -> Router {
+    let public_dir = state.config.server.public_dir();
+    let static_files_service = ServeDir::new(public_dir).append_index_html_on_directories(false);
+
+    let mut router = Router::new()
+        .route("/healthz", get(handlers::health_handlers::healthz_handler))
+        .route("/readyz", get(handlers::health_handlers::readyz_handler))
+        .route(
+            "/admin/posts/preview",
+            post(handlers::post_handlers::preview_block_handler)
+        )
+        .route(
+            "/",
+            get(handlers::index_handler::serve_index_page_handler),
+        )
+        .route("/mario", get(handlers::mario_index_handler::serve_index_page_handler))
+        .route(
+            "/api/hello",
+            get(handlers::api_handlers::hello_json_api_handler),
+        )
+        .route(
+            "/api/posts/:id/blocks",
+            post(handlers::post_handlers::create_block_handler),
+        )
+        .route(
+            "/api/posts/:id/blocks/:block_id",
+            post(handlers::post_handlers::update_block_handler)
+                .delete(handlers::post_handlers::delete_block_handler),
+        )
+        .route(
+            "/api/posts/:id/blocks/:block_id/move",
+            post(handlers::post_handlers::move_block_handler),
+        )
+        .route("/counter", get(handlers::counter_handler::page_handler))
+        .route("/api/counter/:id", post(handlers::counter_handler::create_handler))
+        .route("/rpc", get(handlers::rpc_handlers::rpc_handler))
+        .route("/dev/reload", get(handlers::dev_handlers::reload_handler))
+        .route("/api/schema", get(handlers::schema_handlers::list_handler))
+        .route("/api/schema/:name", get(handlers::schema_handlers::schema_handler))
+        .merge(state.documents.routes())
+        .fallback_service(static_files_service)
+        .layer(middleware::from_fn_with_state(state.clone(), error::render_errors));
+    if state.live_reload.is_some() {
+        // Outside `render_errors`, so error pages get the listener too.
+        router = router.layer(middleware::from_fn_with_state(state.clone(), dev::inject_listener));
+    }
+    // Layers run outermost-last: the id is assigned first, then the request
+    // span opens with it, and the response carries it back.
+    router
+        .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::request_span)
+                .on_response(telemetry::record_response),
+        )
+        .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+        .with_state(state)
+}
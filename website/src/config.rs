@@ -0,0 +1,398 @@
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// Prefix for environment overrides, e.g. `AUTEUR__DATABASE__PASSWORD=secret`.
+pub const ENV_PREFIX: &str = "AUTEUR";
+/// Selects the optional per-environment file, e.g. `AUTEUR_ENV=production`
+/// layers `config/production.toml` on top of `config/default.toml`.
+pub const ENV_PROFILE: &str = "AUTEUR_ENV";
+/// Points at an explicit config file instead of the `config/` directory.
+pub const ENV_CONFIG_FILE: &str = "AUTEUR_CONFIG";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub templates: TemplatesConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+    #[serde(default)]
+    pub dev: DevConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Directory served as the static-file fallback, relative to the crate root.
+    pub public_dir: PathBuf,
+    /// How long shutdown waits for open WebSockets to close.
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseConfig {
+    /// `mem://`, `surrealkv://<path>` or `ws://<host>:<port>`; see [`Engine`].
+    pub endpoint: String,
+    pub username: String,
+    pub password: String,
+    pub namespace: String,
+    pub database: String,
+    /// Apply pending migrations before serving. On by default.
+    #[serde(default = "default_true")]
+    pub migrate_on_startup: bool,
+    /// First delay between connection attempts; doubles up to `retry_max_ms`.
+    #[serde(default = "default_retry_initial_ms")]
+    pub retry_initial_ms: u64,
+    #[serde(default = "default_retry_max_ms")]
+    pub retry_max_ms: u64,
+    /// Connection attempts at startup before giving up; 0 retries forever.
+    #[serde(default)]
+    pub connect_attempts: u32,
+    /// How often the connection is checked once serving.
+    #[serde(default = "default_health_check_secs")]
+    pub health_check_secs: u64,
+}
+
+/// Storage engine selected by the scheme of `database.endpoint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Engine {
+    /// Embedded, in-memory store. Nothing survives a restart.
+    Memory,
+    /// Embedded SurrealKV store in the given directory.
+    File(PathBuf),
+    /// A SurrealDB server reached over `ws://` or `wss://`.
+    Remote(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplatesConfig {
+    /// Tera glob, relative to the crate root.
+    pub glob: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// `tracing` filter directives, e.g. `info,website=debug`. `RUST_LOG`
+    /// takes precedence when set.
+    #[serde(default = "default_log_filter")]
+    pub filter: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self { format: LogFormat::default(), filter: default_log_filter() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable, multi-line; for a terminal.
+    #[default]
+    Pretty,
+    /// One JSON object per line; for log shippers.
+    Json,
+}
+
+/// Development conveniences, off by default; `AUTEUR_ENV=development`
+/// turns them on.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DevConfig {
+    /// Watch templates and `public/`, reload Tera on change and tell open
+    /// browser tabs to refresh.
+    #[serde(default)]
+    pub live_reload: bool,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Load(config::ConfigError),
+    Invalid { key: &'static str, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Load(e) => write!(f, "could not load configuration: {}", e),
+            ConfigError::Invalid { key, reason } => {
+                write!(f, "invalid configuration value `{}`: {}", key, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<config::ConfigError> for ConfigError {
+    fn from(e: config::ConfigError) -> Self {
+        ConfigError::Load(e)
+    }
+}
+
+impl Config {
+    /// Loads `config/default.toml`, then `config/{AUTEUR_ENV}.toml` if set
+    /// (or the single file named by `AUTEUR_CONFIG`), then `AUTEUR__*`
+    /// environment variables, and validates the result.
+    pub fn load() -> Result<Self, ConfigError> {
+        let root = crate_root();
+        let mut builder = config::Config::builder();
+
+        if let Ok(file) = std::env::var(ENV_CONFIG_FILE) {
+            builder = builder.add_source(config::File::with_name(&file).required(true));
+        } else {
+            let dir = root.join("config");
+            builder = builder.add_source(config::File::from(dir.join("default.toml")).required(true));
+            if let Ok(profile) = std::env::var(ENV_PROFILE) {
+                builder = builder
+                    .add_source(config::File::from(dir.join(format!("{}.toml", profile))).required(true));
+            }
+        }
+
+        let settings = builder
+            .add_source(config::Environment::with_prefix(ENV_PREFIX).separator("__"))
+            .build()?;
+
+        let config: Config = settings.try_deserialize()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses a single TOML document without touching the environment.
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        let config: Config = config::Config::builder()
+            .add_source(config::File::from_str(source, config::FileFormat::Toml))
+            .build()?
+            .try_deserialize()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.server
+            .host
+            .parse::<IpAddr>()
+            .map_err(|e| invalid("server.host", e.to_string()))?;
+        if self.server.port == 0 {
+            return Err(invalid("server.port", "must be between 1 and 65535"));
+        }
+        let engine = self.database.engine()?;
+        if matches!(engine, Engine::Remote(_)) && self.database.username.trim().is_empty() {
+            return Err(invalid("database.username", "must not be empty for a remote endpoint"));
+        }
+        if self.database.namespace.trim().is_empty() {
+            return Err(invalid("database.namespace", "must not be empty"));
+        }
+        if self.database.database.trim().is_empty() {
+            return Err(invalid("database.database", "must not be empty"));
+        }
+        if self.database.retry_initial_ms == 0 || self.database.retry_max_ms < self.database.retry_initial_ms {
+            return Err(invalid(
+                "database.retry_initial_ms",
+                "must be non-zero and no larger than database.retry_max_ms",
+            ));
+        }
+        if self.database.health_check_secs == 0 {
+            return Err(invalid("database.health_check_secs", "must be at least 1"));
+        }
+        if self.templates.glob.trim().is_empty() {
+            return Err(invalid("templates.glob", "must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+impl ServerConfig {
+    pub fn socket_addr(&self) -> SocketAddr {
+        // `validate` has already checked that the host parses.
+        let ip: IpAddr = self.host.parse().expect("server.host validated at load");
+        SocketAddr::new(ip, self.port)
+    }
+
+    pub fn public_dir(&self) -> PathBuf {
+        resolve(&self.public_dir)
+    }
+
+    pub fn shutdown_grace(&self) -> Duration {
+        Duration::from_secs(self.shutdown_grace_secs)
+    }
+}
+
+impl DatabaseConfig {
+    pub fn engine(&self) -> Result<Engine, ConfigError> {
+        let endpoint = self.endpoint.trim();
+        let Some((scheme, rest)) = endpoint.split_once("://") else {
+            return Err(invalid("database.endpoint", format!("`{}` has no scheme", endpoint)));
+        };
+        match scheme {
+            "mem" | "memory" => Ok(Engine::Memory),
+            "surrealkv" | "file" if rest.is_empty() => {
+                Err(invalid("database.endpoint", "a file endpoint needs a path"))
+            }
+            "surrealkv" | "file" => Ok(Engine::File(resolve(Path::new(rest)))),
+            "ws" | "wss" if rest.is_empty() => {
+                Err(invalid("database.endpoint", "a remote endpoint needs a host"))
+            }
+            "ws" | "wss" => Ok(Engine::Remote(endpoint.to_string())),
+            other => Err(invalid(
+                "database.endpoint",
+                format!("unsupported scheme `{}` (expected mem, surrealkv or ws)", other),
+            )),
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn retry_initial(&self) -> Duration {
+        Duration::from_millis(self.retry_initial_ms)
+    }
+
+    pub fn retry_max(&self) -> Duration {
+        Duration::from_millis(self.retry_max_ms)
+    }
+
+    pub fn health_check_interval(&self) -> Duration {
+        Duration::from_secs(self.health_check_secs)
+    }
+}
+
+impl Engine {
+    /// The address handed to `surrealdb::engine::any::connect`.
+    pub fn endpoint(&self) -> String {
+        match self {
+            Engine::Memory => "mem://".to_string(),
+            Engine::File(path) => format!("surrealkv://{}", path.display()),
+            Engine::Remote(url) => url.clone(),
+        }
+    }
+}
+
+impl TemplatesConfig {
+    pub fn glob(&self) -> String {
+        resolve(Path::new(&self.glob)).to_string_lossy().into_owned()
+    }
+
+    /// The directory the glob starts from, e.g. `src/templates` for
+    /// `src/templates/**/*.html`.
+    pub fn dir(&self) -> PathBuf {
+        let literal: PathBuf = Path::new(&self.glob)
+            .components()
+            .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[', '{']))
+            .collect();
+        resolve(&literal)
+    }
+}
+
+fn default_shutdown_grace_secs() -> u64 {
+    10
+}
+
+fn default_retry_initial_ms() -> u64 {
+    250
+}
+
+fn default_retry_max_ms() -> u64 {
+    30_000
+}
+
+fn default_health_check_secs() -> u64 {
+    5
+}
+
+fn default_log_filter() -> String {
+    "info".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn invalid(key: &'static str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid { key, reason: reason.into() }
+}
+
+fn crate_root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn resolve(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        crate_root().join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT_TOML: &str = include_str!("../config/default.toml");
+
+    #[test]
+    fn default_file_is_valid() {
+        let config = Config::from_toml(DEFAULT_TOML).expect("default.toml should load");
+        assert_eq!(config.server.socket_addr(), SocketAddr::from(([127, 0, 0, 1], 3000)));
+        assert_eq!(config.database.namespace, "test");
+        assert!(config.templates.glob().ends_with("src/templates/**/*.html"));
+        assert!(config.templates.dir().ends_with("src/templates"));
+        assert!(!config.dev.live_reload);
+        assert_eq!(config.log.format, LogFormat::Pretty);
+        assert_eq!(
+            config.database.engine().unwrap(),
+            Engine::Remote("ws://127.0.0.1:8000".into())
+        );
+    }
+
+    #[test]
+    fn parses_embedded_engines() {
+        let memory = DEFAULT_TOML.replace("ws://127.0.0.1:8000", "mem://");
+        let config = Config::from_toml(&memory).unwrap();
+        assert_eq!(config.database.engine().unwrap(), Engine::Memory);
+
+        let file = DEFAULT_TOML.replace("ws://127.0.0.1:8000", "surrealkv://data/auteur.db");
+        let config = Config::from_toml(&file).unwrap();
+        let Engine::File(path) = config.database.engine().unwrap() else {
+            panic!("expected a file engine");
+        };
+        assert!(path.is_absolute() && path.ends_with("data/auteur.db"));
+    }
+
+    #[test]
+    fn rejects_unknown_engine() {
+        let source = DEFAULT_TOML.replace("ws://127.0.0.1:8000", "rocksdb://data");
+        let err = Config::from_toml(&source).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { key: "database.endpoint", .. }));
+    }
+
+    #[test]
+    fn rejects_backoff_that_starts_above_its_cap() {
+        let source = DEFAULT_TOML.replace("retry_max_ms = 30000", "retry_max_ms = 100");
+        let err = Config::from_toml(&source).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { key: "database.retry_initial_ms", .. }));
+    }
+
+    #[test]
+    fn rejects_bad_host() {
+        let source = DEFAULT_TOML.replace("host = \"127.0.0.1\"", "host = \"localhost:3000\"");
+        let err = Config::from_toml(&source).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::Invalid { key: "server.host", .. }),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn rejects_missing_section() {
+        let err = Config::from_toml("[server]\nhost = \"127.0.0.1\"\nport = 3000").unwrap_err();
+        assert!(matches!(err, ConfigError::Load(_)), "unexpected error: {}", err);
+    }
+}
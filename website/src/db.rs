@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use surrealdb::engine::any::{self, Any};
+use surrealdb::opt::auth::Root;
+use surrealdb::Surreal;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{DatabaseConfig, Engine};
+use crate::telemetry::db_call;
+
+/// Engine-agnostic SurrealDB handle. The concrete engine (in-memory,
+/// SurrealKV file or remote WebSocket) is picked at runtime from config.
+pub type Db = Surreal<Any>;
+
+/// Opens the configured engine, signs in when talking to a remote server and
+/// selects the namespace and database.
+pub async fn connect(config: &DatabaseConfig, engine: &Engine) -> Result<Db, surrealdb::Error> {
+    let db = any::connect(engine.endpoint()).await?;
+
+    // Embedded engines run without authentication; only a server needs root credentials.
+    if let Engine::Remote(_) = engine {
+        db.signin(Root {
+            username: &config.username,
+            password: &config.password,
+        })
+        .await?;
+    }
+
+    db.use_ns(&config.namespace).use_db(&config.database).await?;
+    Ok(db)
+}
+
+/// [`connect`], retried with exponential backoff until it succeeds or
+/// `database.connect_attempts` (when non-zero) runs out.
+pub async fn connect_with_retry(
+    config: &DatabaseConfig,
+    engine: &Engine,
+) -> Result<Db, surrealdb::Error> {
+    let mut backoff = Backoff::new(config.retry_initial(), config.retry_max());
+    let mut attempt = 1;
+    loop {
+        match connect(config, engine).await {
+            Ok(db) => return Ok(db),
+            Err(e) if config.connect_attempts == 0 || attempt < config.connect_attempts => {
+                let delay = backoff.next_delay();
+                tracing::warn!(attempt, error = %e, ?delay, "could not connect to SurrealDB; retrying");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Exponential backoff: each delay doubles, capped at `max`.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    next: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, next: initial, max }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+/// What the health monitor last saw of the database connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Link {
+    pub connected: bool,
+    /// Bumped on every reconnect. Live queries opened under an older
+    /// generation died with the old connection and must be re-issued.
+    pub generation: u64,
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self { connected: true, generation: 0 }
+    }
+}
+
+/// Watches the connection state published by [`DbHealth::spawn`].
+///
+/// The WebSocket driver reconnects on its own and replays `signin` and
+/// `use`, so queries resume transparently once the server is back. It does
+/// not replay live queries: subscribers watch [`Link::generation`] for that.
+#[derive(Clone)]
+pub struct DbHealth {
+    link: watch::Receiver<Link>,
+}
+
+impl Default for DbHealth {
+    fn default() -> Self {
+        Self::new(Link::default()).1
+    }
+}
+
+impl DbHealth {
+    /// A health handle fed by the returned sender instead of a monitor.
+    pub fn new(link: Link) -> (watch::Sender<Link>, Self) {
+        let (tx, rx) = watch::channel(link);
+        (tx, Self { link: rx })
+    }
+
+    /// Pings `db` every `interval` until `shutdown` fires, publishing each
+    /// change of state.
+    pub fn spawn(db: Db, interval: Duration, shutdown: CancellationToken) -> Self {
+        let (tx, health) = Self::new(Link::default());
+        tokio::spawn(async move {
+            let mut session = session_id(&db, interval).await.ok();
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(interval) => {}
+                }
+                let seen = session_id(&db, interval).await;
+                let mut link = *tx.borrow();
+                match seen {
+                    Ok(id) => {
+                        // A new session id means the driver reconnected between two pings.
+                        if !link.connected || session.as_ref().is_some_and(|s| *s != id) {
+                            link.generation += 1;
+                            tracing::info!(generation = link.generation, "SurrealDB connection restored");
+                        }
+                        link.connected = true;
+                        session = Some(id);
+                    }
+                    Err(reason) => {
+                        if link.connected {
+                            tracing::error!(%reason, "SurrealDB connection lost; reconnecting");
+                        }
+                        link.connected = false;
+                    }
+                }
+                tx.send_if_modified(|current| {
+                    let changed = *current != link;
+                    *current = link;
+                    changed
+                });
+            }
+        });
+        health
+    }
+
+    pub fn current(&self) -> Link {
+        *self.link.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Link> {
+        self.link.clone()
+    }
+}
+
+/// `$session.id` identifies the server-side connection; embedded engines have none.
+async fn session_id(db: &Db, timeout: Duration) -> Result<Option<String>, String> {
+    let query = db_call("ping", "", async {
+        let id: Option<String> = db.query("RETURN $session.id").await?.check()?.take(0)?;
+        Ok::<_, surrealdb::Error>(id)
+    });
+    match tokio::time::timeout(timeout, query).await {
+        Ok(Ok(id)) => Ok(id),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer within {:?}", timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(350));
+        let delays: Vec<u128> = (0..4).map(|_| backoff.next_delay().as_millis()).collect();
+        assert_eq!(delays, [100, 200, 350, 350]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn monitor_reports_an_unreachable_database() {
+        let shutdown = CancellationToken::new();
+        let health = DbHealth::spawn(Surreal::init(), Duration::from_millis(10), shutdown.clone());
+        let mut link = health.subscribe();
+        tokio::time::timeout(Duration::from_secs(1), link.wait_for(|l| !l.connected))
+            .await
+            .expect("monitor notices the failed ping")
+            .unwrap();
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn monitor_keeps_an_embedded_database_connected() {
+        let db = any::connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let shutdown = CancellationToken::new();
+        let health = DbHealth::spawn(db, Duration::from_millis(10), shutdown.clone());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(health.current(), Link { connected: true, generation: 0 });
+        shutdown.cancel();
+    }
+}
@@ -0,0 +1,199 @@
+//! Development mode (`dev.live_reload`): watches the templates and `public/`,
+//! reloads Tera when a template changes and tells open browser tabs to
+//! refresh over `/dev/reload`.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::Response;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tera::Context;
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
+
+use crate::templates::Templates;
+use crate::AppState;
+
+/// Editors write a file in several steps; changes closer together than this
+/// are handled as one.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Partial injected into every HTML page; holds the `<dev-reload-listener>`.
+pub const LISTENER_TEMPLATE: &str = "dev/reload.html";
+
+/// Fans out one message per batch of file changes to every `/dev/reload`
+/// socket.
+#[derive(Clone)]
+pub struct LiveReload {
+    reloads: broadcast::Sender<()>,
+}
+
+impl Default for LiveReload {
+    fn default() -> Self {
+        Self { reloads: broadcast::channel(16).0 }
+    }
+}
+
+impl LiveReload {
+    /// Starts watching `template_dir` and `public_dir` until `shutdown` fires.
+    pub fn watch(
+        templates: Arc<Templates>,
+        template_dir: PathBuf,
+        public_dir: PathBuf,
+        shutdown: CancellationToken,
+    ) -> notify::Result<Self> {
+        let live_reload = Self::default();
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                let _ = tx.send(event);
+            }
+            Err(e) => tracing::warn!(error = %e, "file watcher error"),
+        })?;
+        watcher.watch(&template_dir, RecursiveMode::Recursive)?;
+        watcher.watch(&public_dir, RecursiveMode::Recursive)?;
+
+        let reloads = live_reload.reloads.clone();
+        tokio::spawn(async move {
+            // Owned by the task so watching stops with it.
+            let _watcher = watcher;
+            loop {
+                let mut paths = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    event = rx.recv() => match event {
+                        Some(event) => changed_paths(event),
+                        None => break,
+                    },
+                };
+                tokio::time::sleep(DEBOUNCE).await;
+                while let Ok(event) = rx.try_recv() {
+                    paths.extend(changed_paths(event));
+                }
+                if paths.is_empty() {
+                    continue;
+                }
+
+                if paths.iter().any(|p| p.starts_with(&template_dir)) {
+                    match templates.reload() {
+                        Ok(()) => tracing::info!("templates reloaded"),
+                        Err(e) => {
+                            // Keep the tabs on the last good render until the template is fixed.
+                            tracing::error!(error = ?e, "template reload failed");
+                            continue;
+                        }
+                    }
+                }
+                // No receivers just means no tab is open.
+                let _ = reloads.send(());
+            }
+        });
+        Ok(live_reload)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.reloads.subscribe()
+    }
+}
+
+/// Paths touched by a create, modify or remove; reads and metadata-only
+/// events are ignored.
+fn changed_paths(event: Event) -> Vec<PathBuf> {
+    match event.kind {
+        EventKind::Create(_) | EventKind::Remove(_) => event.paths,
+        EventKind::Modify(notify::event::ModifyKind::Metadata(_)) => vec![],
+        EventKind::Modify(_) => event.paths,
+        _ => vec![],
+    }
+    .into_iter()
+    .filter(|p| !is_editor_noise(p))
+    .collect()
+}
+
+/// Swap files, backups and `.DS_Store` that editors and Finder leave around.
+fn is_editor_noise(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name.starts_with(".#") || name.ends_with('~') || name.ends_with(".swp") || name == ".DS_Store"
+}
+
+/// Middleware mounted in dev mode: appends the reload listener to every
+/// HTML response, just before `</body>`.
+pub async fn inject_listener(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if !is_html {
+        return response;
+    }
+
+    let listener = match app_state.templates.render(LISTENER_TEMPLATE, &Context::new()) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(error = ?e, "could not render {}", LISTENER_TEMPLATE);
+            return response;
+        }
+    };
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let html = insert_before_body_end(&String::from_utf8_lossy(&bytes), &listener);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(html))
+}
+
+fn insert_before_body_end(html: &str, snippet: &str) -> String {
+    match html.rfind("</body>") {
+        Some(at) => format!("{}{}{}", &html[..at], snippet, &html[at..]),
+        None => format!("{}{}", html, snippet),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet_goes_before_the_closing_body_tag() {
+        let html = insert_before_body_end("<body><p>hi</p></body></html>", "<x></x>");
+        assert_eq!(html, "<body><p>hi</p><x></x></body></html>");
+        assert_eq!(insert_before_body_end("<p>hi</p>", "<x></x>"), "<p>hi</p><x></x>");
+    }
+
+    #[tokio::test]
+    async fn template_change_reloads_and_notifies() {
+        let root = std::env::temp_dir().join(format!("auteur-dev-{}", std::process::id()));
+        let (template_dir, public_dir) = (root.join("templates"), root.join("public"));
+        std::fs::create_dir_all(&template_dir).unwrap();
+        std::fs::create_dir_all(&public_dir).unwrap();
+        std::fs::write(template_dir.join("page.html"), "v1").unwrap();
+
+        let glob = format!("{}/**/*.html", template_dir.display());
+        let templates = Arc::new(Templates::load(&glob).unwrap());
+        let shutdown = CancellationToken::new();
+        let live_reload =
+            LiveReload::watch(templates.clone(), template_dir.clone(), public_dir, shutdown.clone()).unwrap();
+        let mut reloads = live_reload.subscribe();
+
+        std::fs::write(template_dir.join("page.html"), "v2").unwrap();
+        tokio::time::timeout(Duration::from_secs(5), reloads.recv())
+            .await
+            .expect("a reload after the write")
+            .unwrap();
+        assert_eq!(templates.render("page.html", &Context::new()).unwrap(), "v2");
+
+        shutdown.cancel();
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,256 @@
+//! Document types and the [`Registry`] that serves them. A type is
+//! registered once, with its repository, and gets:
+//!
+//! - `GET /api/<table>` to list and `POST /api/<table>` to create;
+//! - `GET`, `POST` (replace) and `DELETE` on `/api/<table>/:id`;
+//! - the admin list at `/admin/<table>/` and editors at
+//!   `/admin/<table>/new` and `/admin/<table>/:id`, which also take the
+//!   editor's form posts.
+//!
+//! Writes are checked against the type's `#[validate(...)]` rules first.
+//! `/api/schema` lists the registered types, and `/api/schema/<name>` serves
+//! each one's JSON Schema, as [`json_schema`] builds it.
+
+use std::sync::Arc;
+
+use axum::routing::get;
+use axum::{Extension, Router};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use surrealdb::sql::Thing;
+use tera::Context;
+
+use crate::db::Db;
+use crate::handlers::{document_handlers, post_handlers};
+use crate::repository::{DocumentRepository, PostRepository, Record, SurrealDocumentRepository};
+use crate::schema::Post;
+use crate::schema_form::{self, Rejected};
+use crate::schema_v2::{Author, Page};
+use crate::validation::{self, Validate};
+use crate::{fields, schema, widgets, AppState};
+
+/// A type the registry can serve.
+pub trait Document: Record + JsonSchema + Validate {
+    /// Singular, for the admin: "Page".
+    const LABEL: &'static str;
+    /// Plural, for the admin: "Pages".
+    const PLURAL: &'static str;
+    /// Renders the admin list, with `document_type`, `documents` and `rows`
+    /// (`key` and `title` of each).
+    const LIST_TEMPLATE: &'static str = "admin/documents/index.html";
+    /// Renders the admin editor, with `document_type`, `document`, `key`,
+    /// `title`, and the generic editor's `fields` (see [`crate::schema_form`]).
+    /// New documents are edited with it too, without `document` and `key`.
+    const EDIT_TEMPLATE: &'static str = "admin/documents/edit.html";
+
+    /// What the admin calls the document.
+    fn title(&self) -> String;
+
+    /// Adds what a custom [`Document::EDIT_TEMPLATE`] needs to edit
+    /// `document`, or a new one when it is `None`.
+    fn edit_context(_document: Option<&Self>, _context: &mut Context) {}
+
+    /// Reads the editor's form post, `(name, text)` pairs named by path,
+    /// and validates it. The generic editor's is decoded by the type's JSON
+    /// Schema ([`schema_form::decode`]); a custom editor may name its inputs
+    /// otherwise.
+    fn from_form(pairs: &[(String, String)]) -> Result<Self, Rejected> {
+        schema_form::decode(&json_schema::<Self>(), pairs)
+    }
+
+    /// The document a custom [`Document::EDIT_TEMPLATE`] shows again, with
+    /// the errors, after [`Document::from_form`] rejected a save. The
+    /// generic editor refills its fields from the input itself.
+    fn refill(_rejected: &Rejected) -> Option<Self> {
+        None
+    }
+}
+
+/// A registered type, as templates and listings see it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocumentType {
+    /// The name of its JSON Schema: `Page`.
+    pub name: String,
+    pub table: &'static str,
+    pub label: &'static str,
+    pub plural: &'static str,
+}
+
+impl DocumentType {
+    pub fn of<T: Document>() -> Self {
+        Self { name: T::schema_name(), table: T::TABLE, label: T::LABEL, plural: T::PLURAL }
+    }
+}
+
+#[derive(Default)]
+pub struct Registry {
+    /// Each type with its [`json_schema`].
+    types: Vec<(DocumentType, fn() -> RootSchema)>,
+    routes: Router<Arc<AppState>>,
+}
+
+impl Registry {
+    /// Adds `T`'s routes, served from `repository`.
+    ///
+    /// # Panics
+    ///
+    /// If a type with the same table is already registered.
+    pub fn register<T: Document>(mut self, repository: Arc<dyn DocumentRepository<T>>) -> Self {
+        assert!(self.get(T::TABLE).is_none(), "`{}` is registered twice", T::TABLE);
+        let api = format!("/api/{}", T::TABLE);
+        let admin = format!("/admin/{}", T::TABLE);
+        let routes = Router::new()
+            .route(
+                &api,
+                get(document_handlers::list_handler::<T>).post(document_handlers::create_handler::<T>),
+            )
+            .route(
+                &format!("{}/:id", api),
+                get(document_handlers::get_handler::<T>)
+                    .post(document_handlers::update_handler::<T>)
+                    .delete(document_handlers::delete_handler::<T>),
+            )
+            .route(&format!("{}/", admin), get(document_handlers::admin_list_handler::<T>))
+            .route(
+                &format!("{}/new", admin),
+                get(document_handlers::admin_new_handler::<T>).post(document_handlers::admin_save_handler::<T>),
+            )
+            .route(
+                &format!("{}/:id", admin),
+                get(document_handlers::admin_edit_handler::<T>).post(document_handlers::admin_save_handler::<T>),
+            )
+            .layer(Extension(repository));
+        self.types.push((DocumentType::of::<T>(), json_schema::<T>));
+        self.routes = self.routes.merge(routes);
+        self
+    }
+
+    /// In the order they were registered.
+    pub fn types(&self) -> impl Iterator<Item = &DocumentType> {
+        self.types.iter().map(|(t, _)| t)
+    }
+
+    pub fn get(&self, table: &str) -> Option<&DocumentType> {
+        self.types().find(|t| t.table == table)
+    }
+
+    /// The JSON Schema of the type named `name`.
+    pub fn schema(&self, name: &str) -> Option<RootSchema> {
+        self.types.iter().find(|(t, _)| t.name == name).map(|(_, schema)| schema())
+    }
+
+    /// The routes of every registered type, for [`crate::app`].
+    pub fn routes(&self) -> Router<Arc<AppState>> {
+        self.routes.clone()
+    }
+}
+
+/// `T`'s JSON Schema as the editor sees it: with the validation rules
+/// `schemars` does not read, and the widgets its fields picked.
+pub fn json_schema<T: Document>() -> RootSchema {
+    let mut root = validation::json_schema::<T>(&schema::definitions());
+    widgets::registry().apply::<T>(&mut root);
+    root
+}
+
+/// Every document type the site has: posts through `posts`, the rest in
+/// their own SurrealDB tables.
+pub fn registry(db: &Arc<Db>, posts: Arc<dyn PostRepository>) -> Registry {
+    Registry::default()
+        .register::<Post>(posts)
+        .register::<Page>(Arc::new(SurrealDocumentRepository::new(db.clone())))
+        .register::<Author>(Arc::new(SurrealDocumentRepository::new(db.clone())))
+}
+
+impl Document for Post {
+    const LABEL: &'static str = "Post";
+    const PLURAL: &'static str = "Posts";
+    const LIST_TEMPLATE: &'static str = "admin/posts/index.html";
+    const EDIT_TEMPLATE: &'static str = "admin/posts/[id].html";
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    /// A new post starts with the default blocks.
+    fn edit_context(post: Option<&Self>, context: &mut Context) {
+        let blank = Post { id: None, title: String::new(), blocks: vec![] };
+        post_handlers::editor_context(post.unwrap_or(&blank), context);
+    }
+
+    /// The post editor names its inputs by field definition:
+    /// `blocks[0].Quote.cite`, and `blocks[0].id` for a block's key.
+    fn from_form(pairs: &[(String, String)]) -> Result<Self, Rejected> {
+        let input = schema_form::tree(pairs);
+        Post::from_editor(&schema::definitions(), &input)
+            .and_then(|post| post.validate().map(|()| post))
+            .map_err(|errors| Rejected { input: as_lists(&input), errors })
+    }
+
+    fn refill(rejected: &Rejected) -> Option<Self> {
+        Some(Post::refill(&schema::definitions(), &rejected.input))
+    }
+}
+
+/// `input` with each object keyed by indexes as an array, in index order.
+fn as_lists(input: &Value) -> Value {
+    match input {
+        Value::Object(object) if !object.is_empty() => match fields::items(input) {
+            Some(items) => items.into_iter().map(|(_, item)| as_lists(item)).collect(),
+            None => object.iter().map(|(key, value)| (key.clone(), as_lists(value))).collect(),
+        },
+        _ => input.clone(),
+    }
+}
+
+impl Record for Page {
+    const TABLE: &'static str = "pages";
+    fn id(&self) -> Option<&Thing> {
+        self.id.as_ref()
+    }
+    fn set_id(&mut self, id: Thing) {
+        self.id = Some(id);
+    }
+}
+
+impl Document for Page {
+    const LABEL: &'static str = "Page";
+    const PLURAL: &'static str = "Pages";
+
+    fn title(&self) -> String {
+        self.metadata.title.clone().unwrap_or_else(|| "Untitled page".into())
+    }
+}
+
+impl Record for Author {
+    const TABLE: &'static str = "authors";
+    fn id(&self) -> Option<&Thing> {
+        self.id.as_ref()
+    }
+    fn set_id(&mut self, id: Thing) {
+        self.id = Some(id);
+    }
+}
+
+impl Document for Author {
+    const LABEL: &'static str = "Author";
+    const PLURAL: &'static str = "Authors";
+
+    fn title(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "`pages` is registered twice")]
+    fn a_table_is_registered_once() {
+        let pages = || Arc::new(crate::repository::MemoryDocumentRepository::<Page>::default());
+        let _ = Registry::default().register::<Page>(pages()).register::<Page>(pages());
+    }
+}
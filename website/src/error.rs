@@ -0,0 +1,255 @@
+use std::fmt;
+use std::sync::Arc;
+
+use axum::extract::multipart::{MultipartError, MultipartRejection};
+use axum::extract::rejection::{FormRejection, JsonRejection};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{Html, IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use serde_json::Value;
+use tera::Context;
+
+use crate::AppState;
+use crate::repository::RepositoryError;
+use crate::validation::ValidationErrors;
+
+/// The one error type handlers return. Each variant maps to a stable `code`
+/// and a client-safe message; the underlying cause is only ever logged.
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(&'static str),
+    BadRequest(String),
+    /// A write whose fields break their `#[validate(...)]` rules.
+    Validation(ValidationErrors),
+    // Boxed: `surrealdb::Error` would otherwise bloat every `Result<_, AppError>`.
+    Database(Box<surrealdb::Error>),
+    Repository(RepositoryError),
+    Template(Box<tera::Error>),
+    Internal(String),
+}
+
+/// Client-facing error body, shared by the JSON response and the error page.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+/// Attached to every error response so [`render_errors`] can log the cause
+/// and re-render the body for browsers.
+#[derive(Debug, Clone)]
+struct ErrorReport {
+    body: ErrorBody,
+    cause: String,
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Database(_)
+            | AppError::Repository(_)
+            | AppError::Template(_)
+            | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation(_) => "validation_failed",
+            AppError::Database(_) | AppError::Repository(_) => "database_error",
+            AppError::Template(_) => "template_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        let message = match self {
+            AppError::NotFound(what) => format!("{} not found", what),
+            AppError::BadRequest(reason) => reason.clone(),
+            AppError::Validation(_) => "Some fields are not valid.".to_string(),
+            AppError::Database(_) | AppError::Repository(_) => {
+                "The database could not complete the request.".to_string()
+            }
+            AppError::Template(_) => "The page could not be rendered.".to_string(),
+            AppError::Internal(_) => "Something went wrong on our side.".to_string(),
+        };
+        // `[{ "path": "title", "message": "is required" }, ...]`
+        let details = match self {
+            AppError::Validation(errors) => serde_json::to_value(errors).ok(),
+            _ => None,
+        };
+        ErrorBody {
+            code: self.code(),
+            message,
+            details,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(what) => write!(f, "{} not found", what),
+            AppError::BadRequest(reason) => write!(f, "bad request: {}", reason),
+            AppError::Validation(errors) => write!(f, "validation failed: {}", errors),
+            AppError::Database(e) => write!(f, "database error: {}", e),
+            AppError::Repository(e) => write!(f, "repository error: {}", e),
+            // Tera keeps the useful part (which variable, which line) in `source`.
+            AppError::Template(e) => write!(f, "template error: {:?}", e),
+            AppError::Internal(reason) => write!(f, "internal error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<surrealdb::Error> for AppError {
+    fn from(e: surrealdb::Error) -> Self {
+        AppError::Database(Box::new(e))
+    }
+}
+
+impl From<RepositoryError> for AppError {
+    fn from(e: RepositoryError) -> Self {
+        match e {
+            RepositoryError::NotFound(what) => AppError::NotFound(what),
+            e => AppError::Repository(e),
+        }
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        AppError::Validation(errors)
+    }
+}
+
+impl From<tera::Error> for AppError {
+    fn from(e: tera::Error) -> Self {
+        AppError::Template(Box::new(e))
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<FormRejection> for AppError {
+    fn from(rejection: FormRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<MultipartRejection> for AppError {
+    fn from(rejection: MultipartRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<MultipartError> for AppError {
+    fn from(e: MultipartError) -> Self {
+        AppError::BadRequest(e.body_text())
+    }
+}
+
+impl IntoResponse for AppError {
+    /// Defaults to the JSON body; [`render_errors`] swaps in the HTML page for
+    /// browser routes.
+    fn into_response(self) -> Response {
+        let report = ErrorReport {
+            body: self.body(),
+            cause: self.to_string(),
+        };
+        let mut response = (self.status(), Json(report.body.clone())).into_response();
+        response.extensions_mut().insert(report);
+        response
+    }
+}
+
+/// Middleware that logs every [`AppError`] with its request context, then
+/// answers `/api/*` and JSON-accepting clients with `{code, message, details}`
+/// and everyone else with the `error.html` page.
+pub async fn render_errors(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let wants_json = request.uri().path().starts_with("/api/") || accepts_json(request.headers());
+
+    let response = next.run(request).await;
+    let Some(report) = response.extensions().get::<ErrorReport>().cloned() else {
+        return response;
+    };
+
+    // Logged inside the request span, which already carries method, route and request id.
+    let status = response.status();
+    if status.is_server_error() {
+        tracing::error!(code = report.body.code, cause = %report.cause, "request failed");
+    } else {
+        tracing::warn!(code = report.body.code, cause = %report.cause, "request rejected");
+    }
+
+    if wants_json {
+        return response;
+    }
+
+    let mut context = Context::new();
+    context.insert("status", &status.as_u16());
+    context.insert("reason", &status.canonical_reason().unwrap_or("Error"));
+    context.insert("error", &report.body);
+    match app_state.templates.render("error.html", &context) {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(err) => {
+            tracing::error!(error = ?err, "could not render the error page");
+            (status, report.body.message).into_response()
+        }
+    }
+}
+
+fn accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json") && !accept.contains("text/html"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    #[tokio::test]
+    async fn into_response_hides_the_cause() {
+        let err = AppError::Internal("connection pool exhausted".into());
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let report = response.extensions().get::<ErrorReport>().cloned().unwrap();
+        assert!(report.cause.contains("connection pool exhausted"));
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], "internal_error");
+        assert!(!body["message"].as_str().unwrap().contains("pool"));
+        assert!(body["details"].is_null());
+    }
+
+    #[test]
+    fn codes_are_stable() {
+        assert_eq!(AppError::NotFound("Post").code(), "not_found");
+        assert_eq!(AppError::NotFound("Post").status(), StatusCode::NOT_FOUND);
+        assert_eq!(AppError::BadRequest("x".into()).code(), "bad_request");
+        assert_eq!(AppError::Internal("x".into()).code(), "internal_error");
+    }
+}
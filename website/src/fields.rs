@@ -0,0 +1,422 @@
+//! Field definitions: what the editor needs to know about each field of a
+//! content type (label, hint, form type, validation rules). They are declared once on the
+//! Rust type with `#[derive(Fields)]` and `#[field(...)]`, and documents
+//! store only their values.
+
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::form_input;
+use crate::surql::join;
+use crate::validation::{FieldError, Rule, ValidationErrors};
+
+pub use schema_derive::Fields;
+
+/// The kind of input a field is edited with, and what its form value is
+/// parsed as (see [`FormType::parse`]). Serialized externally tagged:
+/// `"Number"`, `{ "Select": [...] }`, `{ "Array": { "Object": "Image" } }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FormType {
+    InputArea,
+    InputText,
+    /// A calendar date, `2025-05-22`.
+    InputDate,
+    /// A UTC date and time, stored as RFC 3339.
+    DateTime,
+    Number,
+    Boolean,
+    Url,
+    /// One of a fixed list of values.
+    Select(&'static [Choice]),
+    /// A record of the given table, as `table:key`.
+    Reference(&'static str),
+    /// A list of values of one form type.
+    Array(&'static FormType),
+    /// A nested object, edited with the fields of the named type.
+    Object(&'static str),
+    /// Any value, entered as JSON text.
+    Json,
+}
+
+/// An option of a [`FormType::Select`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Choice {
+    pub value: &'static str,
+    pub label: &'static str,
+}
+
+/// How the editor shows one field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldDef {
+    /// The field's key in the stored document.
+    pub name: &'static str,
+    pub label: &'static str,
+    pub hint: &'static str,
+    pub form_type: FormType,
+    /// An `Option`: left empty, it is `null`.
+    pub optional: bool,
+    /// From the field's `#[validate(...)]`.
+    pub rules: &'static [Rule],
+}
+
+/// A type whose fields the editor knows how to show.
+pub trait Fields {
+    /// The type's name, e.g. `Header`; blocks use it as their key too.
+    const NAME: &'static str;
+
+    fn fields() -> Vec<FieldDef>;
+
+    /// The definition of one field, by stored name.
+    fn field(name: &str) -> Option<FieldDef> {
+        Self::fields().into_iter().find(|f| f.name == name)
+    }
+
+    /// A value from submitted form input: `form` is a tree of strings (see
+    /// [`FormType::parse`]), and `definitions` must include every type
+    /// nested in `Self`. Fields without a definition, such as
+    /// `#[field(skip)]` ones, are not read, so they need a serde default.
+    fn from_form(definitions: &Definitions, form: &Value) -> Result<Self, ValidationErrors>
+    where
+        Self: Sized + DeserializeOwned,
+    {
+        let mut errors = Vec::new();
+        let value = parse_object(definitions, &Self::fields(), form, "", &mut errors);
+        if !errors.is_empty() {
+            return Err(ValidationErrors(errors));
+        }
+        serde_json::from_value(value).map_err(|e| {
+            ValidationErrors(vec![FieldError { path: String::new(), message: e.to_string() }])
+        })
+    }
+}
+
+impl FormType {
+    /// The typed JSON value for the raw form value `raw`, which is a
+    /// string, an array or an object of strings (an object with index keys
+    /// also counts as an array). Values that are already typed, as in a JSON
+    /// request, are kept. An empty input is `null`, except for text, which
+    /// stays `""`, and an unchecked (missing) `Boolean`, which is `false`.
+    pub fn parse(&self, definitions: &Definitions, raw: &Value, path: &str, errors: &mut Vec<FieldError>) -> Value {
+        let mut fail = |message: String| {
+            errors.push(FieldError { path: path.to_string(), message });
+            Value::Null
+        };
+        let text = match raw {
+            Value::String(text) => text.trim(),
+            _ => "",
+        };
+        match (self, raw) {
+            (FormType::InputText | FormType::InputArea, Value::String(_) | Value::Null) => raw.clone(),
+            (FormType::Boolean, Value::Bool(_)) => raw.clone(),
+            (FormType::Boolean, Value::Null) => Value::Bool(false),
+            (FormType::Boolean, Value::String(_)) if text.is_empty() => Value::Bool(false),
+            (FormType::Boolean, _) => form_input::boolean(text).map(Value::Bool).unwrap_or_else(&mut fail),
+            (FormType::Number, Value::Number(_)) => raw.clone(),
+            (FormType::Array(item), _) => match items(raw) {
+                Some(items) => Value::Array(
+                    items.into_iter().map(|(i, v)| item.parse(definitions, v, &format!("{}[{}]", path, i), errors)).collect(),
+                ),
+                None => fail("must be a list".into()),
+            },
+            (FormType::Object(name), Value::Object(_)) => match definitions.get(name) {
+                Some(fields) => parse_object(definitions, fields, raw, path, errors),
+                None => fail(format!("has no definition for {}", name)),
+            },
+            (_, Value::Null) => Value::Null,
+            (_, Value::String(_)) if text.is_empty() => Value::Null,
+            (FormType::Json, Value::String(_)) => form_input::json(text).unwrap_or_else(&mut fail),
+            (FormType::Json, _) => raw.clone(),
+            (FormType::Number, _) => match form_input::number(text) {
+                // Whole numbers stay integers, so they fit integer fields.
+                Ok(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => Value::from(n as i64),
+                Ok(n) => Value::from(n),
+                Err(message) => fail(message),
+            },
+            (FormType::Url, _) => form_input::url(text).map(Value::String).unwrap_or_else(&mut fail),
+            (FormType::InputDate, _) => form_input::date(text).map(Value::String).unwrap_or_else(&mut fail),
+            (FormType::DateTime, _) => form_input::date_time(text).map(Value::String).unwrap_or_else(&mut fail),
+            (FormType::Select(choices), _) => match choices.iter().find(|c| c.value == text) {
+                Some(choice) => Value::String(choice.value.to_string()),
+                None => {
+                    let values: Vec<&str> = choices.iter().map(|c| c.value).collect();
+                    fail(format!("must be one of {}", values.join(", ")))
+                }
+            },
+            (FormType::Reference(table), _) => match text.split_once(':').unwrap_or((table, text)) {
+                (t, key) if t == *table && !key.is_empty() => Value::String(format!("{}:{}", table, key)),
+                _ => fail(format!("must be a {} record", table)),
+            },
+            (FormType::InputText | FormType::InputArea, _) => fail("must be text".into()),
+            (FormType::Object(_), _) => fail("must be an object".into()),
+        }
+    }
+}
+
+/// The fields of `raw` that have a definition, each parsed by its form type.
+fn parse_object(definitions: &Definitions, fields: &[FieldDef], raw: &Value, path: &str, errors: &mut Vec<FieldError>) -> Value {
+    let empty = Map::new();
+    let raw = raw.as_object().unwrap_or(&empty);
+    let mut object = Map::new();
+    for field in fields {
+        let value = raw.get(field.name).unwrap_or(&Value::Null);
+        let value = match field.form_type.parse(definitions, value, &join(path, field.name), errors) {
+            Value::String(text) if text.is_empty() && field.optional => Value::Null,
+            value => value,
+        };
+        // Leave missing required values to serde, which names them.
+        if !(value.is_null() && !field.optional && !raw.contains_key(field.name)) {
+            object.insert(field.name.to_string(), value);
+        }
+    }
+    Value::Object(object)
+}
+
+/// The items of a list in form input with their indexes: an array, or
+/// `{ "0": a, "1": b }` ordered by index. Missing is empty; anything else
+/// is not a list.
+pub fn items(raw: &Value) -> Option<Vec<(usize, &Value)>> {
+    match raw {
+        Value::Null => Some(vec![]),
+        Value::Array(items) => Some(items.iter().enumerate().collect()),
+        Value::Object(items) => {
+            let mut items: Vec<(usize, &Value)> =
+                items.iter().map(|(k, v)| k.parse().ok().map(|i| (i, v))).collect::<Option<_>>()?;
+            items.sort_by_key(|(i, _)| *i);
+            Some(items)
+        }
+        _ => None,
+    }
+}
+
+/// The definitions of several types, serialized as
+/// `{ "<Type>": { "<field>": { label, hint, form_type, ... } } }` with
+/// fields in declaration order, which is how templates and the editor
+/// script look them up.
+#[derive(Debug, Clone, Default)]
+pub struct Definitions(Vec<(&'static str, Vec<FieldDef>)>);
+
+impl Definitions {
+    pub fn with<T: Fields>(mut self) -> Self {
+        self.0.push((T::NAME, T::fields()));
+        self
+    }
+
+    pub fn get(&self, type_name: &str) -> Option<&[FieldDef]> {
+        self.0.iter().find(|(name, _)| *name == type_name).map(|(_, fields)| fields.as_slice())
+    }
+
+    /// Form input for one of these types, tagged with its name as blocks
+    /// are (`{ "Quote": { ... } }`), with the fields parsed as
+    /// [`Fields::from_form`] parses them.
+    pub fn parse_tagged(&self, raw: &Value, path: &str, errors: &mut Vec<FieldError>) -> Value {
+        let Some((name, value)) = raw.as_object().filter(|raw| raw.len() == 1).and_then(|raw| raw.iter().next()) else {
+            errors.push(FieldError { path: path.to_string(), message: "must be one type's fields, keyed by its name".into() });
+            return Value::Null;
+        };
+        let Some(fields) = self.get(name) else {
+            errors.push(FieldError { path: path.to_string(), message: format!("has no definition for {}", name) });
+            return Value::Null;
+        };
+        let mut object = Map::new();
+        object.insert(name.clone(), parse_object(self, fields, value, &join(path, name), errors));
+        Value::Object(object)
+    }
+}
+
+impl Serialize for Definitions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct ByName<'a>(&'a [FieldDef]);
+
+        impl Serialize for ByName<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.0.len()))?;
+                for field in self.0 {
+                    map.serialize_entry(field.name, field)?;
+                }
+                map.end()
+            }
+        }
+
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, fields) in &self.0 {
+            map.serialize_entry(name, &ByName(fields))?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use crate::reference::Reference;
+    use crate::schema::PostRecord;
+
+    #[derive(Serialize, Fields)]
+    #[allow(dead_code)]
+    struct Article {
+        id: Option<String>,
+        #[field(label = "Headline", hint = "Shown in search results", form_type = InputArea)]
+        #[validate(required)]
+        title: String,
+        #[serde(rename = "published")]
+        published_at: String,
+        #[field(skip)]
+        cache: Vec<u8>,
+    }
+
+    #[test]
+    fn derive_reads_field_attributes() {
+        assert_eq!(Article::NAME, "Article");
+        assert_eq!(
+            Article::fields(),
+            [
+                FieldDef {
+                    name: "title",
+                    label: "Headline",
+                    hint: "Shown in search results",
+                    form_type: FormType::InputArea,
+                    optional: false,
+                    rules: &[Rule::Required],
+                },
+                FieldDef {
+                    name: "published",
+                    label: "Published",
+                    hint: "",
+                    form_type: FormType::InputText,
+                    optional: false,
+                    rules: &[]
+                },
+            ]
+        );
+    }
+
+    #[derive(Serialize, Fields)]
+    #[serde(rename_all = "camelCase")]
+    struct Teaser {
+        og_title: String,
+        #[serde(rename = "url")]
+        canonical_url: String,
+    }
+
+    #[test]
+    fn derive_names_fields_under_rename_all() {
+        let names: Vec<_> = Teaser::fields().iter().map(|field| (field.name, field.label)).collect();
+        assert_eq!(names, [("ogTitle", "Og title"), ("url", "Url")]);
+        let teaser = Teaser { og_title: String::new(), canonical_url: String::new() };
+        let json = serde_json::to_value(teaser).unwrap();
+        assert!(json.get("ogTitle").is_some() && json.get("url").is_some());
+    }
+
+    #[test]
+    fn definitions_serialize_by_type_then_field() {
+        let json = serde_json::to_value(Definitions::default().with::<Article>()).unwrap();
+        assert_eq!(json["Article"]["title"]["label"], "Headline");
+        assert_eq!(json["Article"]["published"]["form_type"], "InputText");
+        assert_eq!(json["Article"]["title"]["rules"], serde_json::json!([{ "rule": "required" }]));
+        let order: Vec<&String> = json["Article"].as_object().unwrap().keys().collect();
+        assert_eq!(order, ["title", "published"]);
+    }
+
+    const SIZES: &[Choice] = &[Choice { value: "s", label: "Small" }, Choice { value: "l", label: "Large" }];
+
+    #[derive(Debug, Serialize, serde::Deserialize, Fields)]
+    struct Listing {
+        #[field(form_type = Select(SIZES))]
+        size: String,
+        seats: u32,
+        public: bool,
+        opens: Option<DateTime<Utc>>,
+        links: Vec<url::Url>,
+        post: Option<Reference<PostRecord>>,
+        venue: Option<Venue>,
+        rooms: Vec<Venue>,
+    }
+
+    #[derive(Debug, Serialize, serde::Deserialize, Fields)]
+    struct Venue {
+        name: String,
+    }
+
+    fn definitions() -> Definitions {
+        Definitions::default().with::<Listing>().with::<Venue>()
+    }
+
+    #[test]
+    fn form_types_follow_the_rust_types() {
+        let types: Vec<(&str, FormType, bool)> =
+            Listing::fields().iter().map(|f| (f.name, f.form_type, f.optional)).collect();
+        assert_eq!(
+            types,
+            [
+                ("size", FormType::Select(SIZES), false),
+                ("seats", FormType::Number, false),
+                ("public", FormType::Boolean, false),
+                ("opens", FormType::DateTime, true),
+                ("links", FormType::Array(&FormType::Url), false),
+                ("post", FormType::Reference("posts"), true),
+                ("venue", FormType::Object("Venue"), true),
+                ("rooms", FormType::Array(&FormType::Object("Venue")), false),
+            ]
+        );
+        let json = serde_json::to_value(definitions()).unwrap();
+        assert_eq!(json["Listing"]["size"]["form_type"]["Select"][1]["label"], "Large");
+        assert_eq!(json["Listing"]["rooms"]["form_type"], serde_json::json!({ "Array": { "Object": "Venue" } }));
+    }
+
+    #[test]
+    fn form_input_is_parsed_into_typed_values() {
+        let form = serde_json::json!({
+            "size": "l",
+            "seats": "120",
+            "public": "on",
+            "opens": "2025-05-22T08:00",
+            "links": { "1": "https://b.example", "0": "https://a.example" },
+            "post": "abc",
+            "venue": { "name": "Hall" },
+            "rooms": [{ "name": "A" }, { "name": "B" }],
+        });
+        let listing = Listing::from_form(&definitions(), &form).unwrap();
+        assert_eq!(listing.seats, 120);
+        assert!(listing.public);
+        assert_eq!(listing.opens.unwrap().to_rfc3339(), "2025-05-22T08:00:00+00:00");
+        assert_eq!(listing.links[0].as_str(), "https://a.example/");
+        assert_eq!(listing.post.unwrap().to_string(), "posts:abc");
+        assert_eq!(listing.venue.unwrap().name, "Hall");
+        assert_eq!(listing.rooms[1].name, "B");
+
+        let form = serde_json::json!({
+            "size": "xl", "seats": "many", "opens": "", "links": ["nope"], "post": "blocks:1", "rooms": [],
+        });
+        let errors = Listing::from_form(&definitions(), &form).unwrap_err().0;
+        let paths: Vec<String> = errors.iter().map(|e| format!("{} {}", e.path, e.message)).collect();
+        assert_eq!(
+            paths,
+            [
+                "size must be one of s, l",
+                "seats must be a number",
+                "links[0] must be a URL",
+                "post must be a posts record",
+            ]
+        );
+    }
+
+    #[test]
+    fn tagged_input_is_parsed_by_its_type() {
+        let definitions = crate::schema::definitions();
+        let mut errors = Vec::new();
+        let raw = serde_json::json!({ "RichText": { "body": "[]" } });
+        assert_eq!(definitions.parse_tagged(&raw, "blocks[0]", &mut errors), serde_json::json!({ "RichText": { "body": [] } }));
+        let raw = serde_json::json!({ "Quote": { "text": "Hi", "attribution": "", "cite": "" } });
+        let quote = definitions.parse_tagged(&raw, "blocks[1]", &mut errors);
+        assert_eq!(quote["Quote"], serde_json::json!({ "text": "Hi", "attribution": null, "cite": null }));
+        assert!(errors.is_empty());
+
+        definitions.parse_tagged(&serde_json::json!({ "RichText": { "body": "[{" } }), "blocks[2]", &mut errors);
+        definitions.parse_tagged(&serde_json::json!({ "Slideshow": {} }), "blocks[3]", &mut errors);
+        let messages: Vec<String> = errors.iter().map(|e| format!("{} {}", e.path, e.message)).collect();
+        assert_eq!(messages, ["blocks[2].RichText.body must be valid JSON", "blocks[3] has no definition for Slideshow"]);
+    }
+}
@@ -0,0 +1,73 @@
+//! The text of one form input read as a typed value, for both kinds of
+//! form: [`crate::fields`] parses by form type and [`crate::schema_form`]
+//! by JSON Schema, with the same rules and the same messages. Each parser
+//! takes text that is trimmed and not empty; what an empty input means is
+//! up to the form.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use url::Url;
+
+/// A checkbox (`on`) or a yes/no select.
+pub fn boolean(text: &str) -> Result<bool, String> {
+    match text {
+        "on" | "true" | "1" => Ok(true),
+        "off" | "false" | "0" => Ok(false),
+        _ => Err("must be yes or no".into()),
+    }
+}
+
+pub fn integer(text: &str) -> Result<i64, String> {
+    text.parse().map_err(|_| "must be a whole number".into())
+}
+
+pub fn number(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        _ => Err("must be a number".into()),
+    }
+}
+
+/// An absolute URL, kept as entered.
+pub fn url(text: &str) -> Result<String, String> {
+    Url::parse(text).map(|_| text.to_string()).map_err(|_| "must be a URL".into())
+}
+
+/// A `date` input, `2025-05-22`.
+pub fn date(text: &str) -> Result<String, String> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").map(|date| date.to_string()).map_err(|_| "must be a date".into())
+}
+
+/// RFC 3339, or a `datetime-local` value taken as UTC, which is how the
+/// forms show times; it has no zone, and seconds only when they are set.
+/// Either way it comes back as RFC 3339 in UTC.
+pub fn date_time(text: &str) -> Result<String, String> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S").map(|at| at.and_utc()))
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M").map(|at| at.and_utc()))
+        .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .map_err(|_| "must be a date and time".into())
+}
+
+/// Any value, as JSON text.
+pub fn json(text: &str) -> Result<Value, String> {
+    serde_json::from_str(text).map_err(|_| "must be valid JSON".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_read_the_same_by_both_forms() {
+        assert_eq!((boolean("on"), boolean("0")), (Ok(true), Ok(false)));
+        assert_eq!(integer("1.5").unwrap_err(), "must be a whole number");
+        assert_eq!(number("inf").unwrap_err(), "must be a number");
+        assert_eq!(url("example.com").unwrap_err(), "must be a URL");
+        assert_eq!(date("2025-5-2").as_deref(), Ok("2025-05-02"));
+        assert_eq!(date_time("2025-05-22T08:00").as_deref(), Ok("2025-05-22T08:00:00Z"));
+        assert_eq!(date_time("2025-05-22T10:00:30+02:00").as_deref(), Ok("2025-05-22T08:00:30Z"));
+        assert_eq!(json("{").unwrap_err(), "must be valid JSON");
+    }
+}
@@ -1,89 +1,56 @@
 use std::sync::Arc;
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
 use axum::{Form};
-use axum::response::{Html, IntoResponse};
-use serde::{Deserialize, Serialize};
-use surrealdb::sql::Thing;
+use axum::response::{Html, Redirect};
+use serde::Deserialize;
 use tera::{Context};
 use crate::AppState;
+use crate::error::AppError;
+use crate::handlers::render;
+use crate::schema::Counter;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Counter {
-    pub id: Option<Thing>,
-    pub count: i32,
-}
-#[derive(Deserialize, Serialize,Debug)]
-pub struct CreateCounter {
-    pub count: i32,
-}
 #[derive(Deserialize)]
 pub struct CounterAction {
     pub action: String,
 }
-pub async fn page_handler( State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+pub async fn page_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Html<String>, AppError> {
     let tera = &app_state.templates;
-    let db   = &app_state.db;
 
-    // 1) Fetch all posts
-    let counter_res: Result<Vec<Counter>, _> = db.select("Counter").await;
-    match counter_res {
-        Ok(posts) => {
-            // 2) Insert into Tera context
-            let mut context = Context::new();
-            context.insert("data", &posts);
+    // 1) Fetch all counters
+    let counters = app_state.counters.list().await?;
 
-            // 3) Render the template
-            match tera.render("counter/index.html", &context) {
-                Ok(html) => Html(html).into_response(),
-                Err(err) => {
-                    eprintln!("Template error: {}", err);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Template error: {}", err),
-                    )
-                        .into_response()
-                }
-            }
-        },
-        Err(e) => {
-            // 4) Handle DB error
-            eprintln!("DB error fetching posts: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error loading posts: {}", e),
-            )
-                .into_response()
-        }
-    }
+    // 2) Insert into Tera context
+    let mut context = Context::new();
+    context.insert("data", &counters);
+
+    // 3) Render the template
+    render(tera, "counter/index.html", &context)
 }
 
+/// Adds `inc`'s 1 or `dec`'s -1 to the counter `id` and goes back to the
+/// counter page. A counter that does not exist yet is created with that count.
 pub async fn create_handler(
     State(app_state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Form(form): Form<CounterAction>,
-) -> impl IntoResponse {
-    match form.action.as_str() {
-        "inc" => println!("incrementing counter"),
-        "dec" => println!("decrement counter"),
-        _ => println!("nada counter")
+) -> Result<Redirect, AppError> {
+    let delta = match form.action.as_str() {
+        "inc" => 1,
+        "dec" => -1,
+        action => return Err(AppError::BadRequest(format!("unknown counter action `{action}`"))),
+    };
+    let counters = &app_state.counters;
+    match counters.get(&id).await? {
+        Some(counter) => {
+            let count = counter.count + delta;
+            counters.update(&id, Counter { count, ..counter }).await?;
+        }
+        None => {
+            counters.create(Counter { id: None, count: delta }).await?;
+        }
     }
-    // let obj = Counter {
-    //     id: None,
-    //     count: payload.count.clone(),
-    // };
-    // let db = &app_state.db;
-    // let result: Result<Vec<Counter>, _> = db.insert("counter").content(obj).await;
-    //
-    // match result {
-    //     Ok(mut counters) => {
-    //         let obj = counters.pop().unwrap();
-    //         (StatusCode::CREATED, Json(obj)).into_response()
-    //     }
-    //     Err(e) => (
-    //         StatusCode::INTERNAL_SERVER_ERROR,
-    //         Json(json!({"error": e.to_string()})),
-    //     )
-    //         .into_response(),
-    // }
-}
\ No newline at end of file
+    tracing::debug!(counter = %id, delta, "counter changed");
+    Ok(Redirect::to("/counter"))
+}
@@ -0,0 +1,43 @@
+use axum::{
+    extract::{ws::{Message, WebSocket}, State, WebSocketUpgrade},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use crate::AppState;
+
+/// `/dev/reload`: sends `"reload"` whenever a watched file changes. 404
+/// unless `dev.live_reload` is on.
+pub async fn reload_handler(State(app_state): State<Arc<AppState>>, ws: WebSocketUpgrade) -> Response {
+    let Some(live_reload) = &app_state.live_reload else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let reloads = live_reload.subscribe();
+    let shutdown = app_state.shutdown.token();
+    let tasks = app_state.shutdown.tasks().clone();
+    ws.on_upgrade(move |socket| tasks.track_future(handle_ws(socket, reloads, shutdown)))
+}
+
+async fn handle_ws(mut socket: WebSocket, mut reloads: broadcast::Receiver<()>, shutdown: CancellationToken) {
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            reload = reloads.recv() => match reload {
+                // A lagging tab still only needs to reload once.
+                Ok(()) | Err(RecvError::Lagged(_)) => {
+                    if socket.send(Message::Text("reload".into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
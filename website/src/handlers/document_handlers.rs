@@ -0,0 +1,258 @@
+//! The routes every registered [`Document`] type gets; see
+//! [`crate::documents`]. Each handler is generic over the type and finds its
+//! repository in an `Extension` the registry layers on the type's routes.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Multipart, Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::{Extension, Form, Json};
+use serde::Serialize;
+use tera::Context;
+
+use crate::documents::{self, Document, DocumentType};
+use crate::error::AppError;
+use crate::handlers::render;
+use crate::repository::DocumentRepository;
+use crate::schema_form::{self, FormField};
+use crate::AppState;
+
+pub type Repository<T> = Extension<Arc<dyn DocumentRepository<T>>>;
+
+/// A form body, urlencoded or multipart, as `(name, text)` pairs in the
+/// order sent. Multipart file parts are skipped.
+pub struct FormInput(pub Vec<(String, String)>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for FormInput {
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let multipart = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("multipart/form-data"));
+        if !multipart {
+            let Form(pairs) = Form::from_request(request, state).await?;
+            return Ok(FormInput(pairs));
+        }
+
+        let mut multipart = Multipart::from_request(request, state).await?;
+        let mut pairs = Vec::new();
+        while let Some(field) = multipart.next_field().await? {
+            let Some(name) = field.name().filter(|_| field.file_name().is_none()).map(str::to_string) else {
+                continue;
+            };
+            pairs.push((name, field.text().await?));
+        }
+        Ok(FormInput(pairs))
+    }
+}
+
+/// One line of the admin list.
+#[derive(Serialize)]
+struct Row {
+    key: String,
+    title: String,
+}
+
+pub async fn list_handler<T: Document>(Extension(repository): Repository<T>) -> Result<Json<Vec<T>>, AppError> {
+    Ok(Json(repository.list().await?))
+}
+
+pub async fn get_handler<T: Document>(
+    Extension(repository): Repository<T>,
+    Path(id): Path<String>,
+) -> Result<Json<T>, AppError> {
+    let document = repository.get(&id).await?;
+    document.map(Json).ok_or(AppError::NotFound(T::LABEL))
+}
+
+pub async fn create_handler<T: Document>(
+    Extension(repository): Repository<T>,
+    payload: Result<Json<T>, JsonRejection>,
+) -> Result<impl IntoResponse, AppError> {
+    let document = without_hidden(payload?.0)?;
+    document.validate()?;
+    let document = repository.create(document).await?;
+    Ok((StatusCode::CREATED, Json(document)))
+}
+
+pub async fn update_handler<T: Document>(
+    Extension(repository): Repository<T>,
+    Path(id): Path<String>,
+    payload: Result<Json<T>, JsonRejection>,
+) -> Result<Json<T>, AppError> {
+    let document = without_hidden(payload?.0)?;
+    document.validate()?;
+    let document = repository.update(&id, document).await?;
+    document.map(Json).ok_or(AppError::NotFound(T::LABEL))
+}
+
+/// `document` without the fields its type's `visibleIf` conditions hide,
+/// as the editor would have saved it.
+fn without_hidden<T: Document>(document: T) -> Result<T, AppError> {
+    let mut value = serde_json::to_value(&document).map_err(|e| AppError::Internal(e.to_string()))?;
+    schema_form::clear_hidden(&documents::json_schema::<T>(), &mut value);
+    serde_json::from_value(value).map_err(|e| AppError::BadRequest(e.to_string()))
+}
+
+pub async fn delete_handler<T: Document>(
+    Extension(repository): Repository<T>,
+    Path(id): Path<String>,
+) -> Result<Json<T>, AppError> {
+    let document = repository.delete(&id).await?;
+    document.map(Json).ok_or(AppError::NotFound(T::LABEL))
+}
+
+pub async fn admin_list_handler<T: Document>(
+    State(app_state): State<Arc<AppState>>,
+    Extension(repository): Repository<T>,
+) -> Result<Html<String>, AppError> {
+    let documents = repository.list().await?;
+    let rows: Vec<Row> = documents
+        .iter()
+        .filter_map(|d| Some(Row { key: d.id()?.id.to_raw(), title: d.title() }))
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("document_type", &DocumentType::of::<T>());
+    context.insert("documents", &documents);
+    context.insert("rows", &rows);
+    render(&app_state.templates, T::LIST_TEMPLATE, &context)
+}
+
+/// The type's editor with nothing in it; saving creates the document.
+pub async fn admin_new_handler<T: Document>(State(app_state): State<Arc<AppState>>) -> Result<Html<String>, AppError> {
+    let mut context = editor_context::<T>(schema_form::form(&documents::json_schema::<T>(), &serde_json::Value::Null));
+    context.insert("title", &format!("New {}", T::LABEL));
+    T::edit_context(None, &mut context);
+    render(&app_state.templates, T::EDIT_TEMPLATE, &context)
+}
+
+pub async fn admin_edit_handler<T: Document>(
+    State(app_state): State<Arc<AppState>>,
+    Extension(repository): Repository<T>,
+    Path(id): Path<String>,
+) -> Result<Html<String>, AppError> {
+    let Some(document) = repository.get(&id).await? else {
+        return Err(AppError::NotFound(T::LABEL));
+    };
+
+    let value = serde_json::to_value(&document).map_err(|e| AppError::Internal(e.to_string()))?;
+    let mut context = editor_context::<T>(schema_form::form(&documents::json_schema::<T>(), &value));
+    context.insert("title", &document.title());
+    context.insert("key", &id);
+    context.insert("document", &document);
+    T::edit_context(Some(&document), &mut context);
+    render(&app_state.templates, T::EDIT_TEMPLATE, &context)
+}
+
+/// Saves the editor's form, posted to `/admin/<table>/new` or
+/// `/admin/<table>/:id`, and redirects to the saved document's editor. When
+/// the input does not make a valid `T`, the editor comes back as a 422 with
+/// the input as entered (see [`Document::refill`]) and the errors.
+pub async fn admin_save_handler<T: Document>(
+    State(app_state): State<Arc<AppState>>,
+    Extension(repository): Repository<T>,
+    id: Option<Path<String>>,
+    FormInput(pairs): FormInput,
+) -> Result<Response, AppError> {
+    let id = id.map(|Path(id)| id);
+    let schema = documents::json_schema::<T>();
+    let rejected = match T::from_form(&pairs) {
+        Ok(document) => {
+            let saved = match &id {
+                Some(id) => repository.update(id, document).await?.ok_or(AppError::NotFound(T::LABEL))?,
+                None => repository.create(document).await?,
+            };
+            let key = saved.id().map(|id| id.id.to_raw()).unwrap_or_default();
+            return Ok(Redirect::to(&format!("/admin/{}/{}", T::TABLE, key)).into_response());
+        }
+        Err(rejected) => rejected,
+    };
+
+    let title = match &id {
+        Some(id) => repository.get(id).await?.ok_or(AppError::NotFound(T::LABEL))?.title(),
+        None => format!("New {}", T::LABEL),
+    };
+    let mut context = editor_context::<T>(schema_form::refill(&schema, &rejected));
+    context.insert("title", &title);
+    context.insert("key", &id);
+    context.insert("errors", &rejected.errors);
+    T::edit_context(T::refill(&rejected).as_ref(), &mut context);
+    let page = render(&app_state.templates, T::EDIT_TEMPLATE, &context)?;
+    Ok((StatusCode::UNPROCESSABLE_ENTITY, page).into_response())
+}
+
+/// What `admin/documents/edit.html` shows: `fields` generated from `T`'s
+/// JSON Schema.
+fn editor_context<T: Document>(fields: Vec<FormField>) -> Context {
+    let mut context = Context::new();
+    context.insert("document_type", &DocumentType::of::<T>());
+    context.insert("fields", &fields);
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::test_state;
+    use crate::repository::MemoryDocumentRepository;
+    use crate::schema::Post;
+    use crate::schema_v2::Author;
+
+    fn post(title: &str) -> Json<Post> {
+        Json(Post { id: None, title: title.into(), blocks: vec![] })
+    }
+
+    fn authors() -> Repository<Author> {
+        Extension(Arc::new(MemoryDocumentRepository::default()))
+    }
+
+    #[tokio::test]
+    async fn create_then_list_posts() {
+        let posts: Repository<Post> = Extension(test_state().posts.clone());
+
+        let response = create_handler(posts.clone(), Ok(post("Hello"))).await.unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let Json(listed) = list_handler(posts).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].title, "Hello");
+    }
+
+    #[tokio::test]
+    async fn blank_titles_are_rejected() {
+        let posts: Repository<Post> = Extension(test_state().posts.clone());
+        let err = create_handler(posts, Ok(post("  "))).await.err().unwrap();
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.body().details.unwrap(), serde_json::json!([{ "path": "title", "message": "is required" }]));
+    }
+
+    #[tokio::test]
+    async fn documents_are_replaced_and_deleted_by_key() {
+        let authors = authors();
+        let author: Author = serde_json::from_value(serde_json::json!({ "name": "Ada" })).unwrap();
+        let created = authors.create(author).await.unwrap();
+        let key = created.id.clone().unwrap().id.to_raw();
+
+        let renamed = Author { name: "Ada Lovelace".into(), ..created };
+        let Json(updated) = update_handler(authors.clone(), Path(key.clone()), Ok(Json(renamed))).await.unwrap();
+        assert_eq!(updated.name, "Ada Lovelace");
+
+        assert!(delete_handler(authors.clone(), Path(key.clone())).await.is_ok());
+        let err = get_handler(authors, Path(key)).await.err().unwrap();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn missing_documents_are_not_found() {
+        let err = admin_edit_handler(State(test_state()), authors(), Path("nope".into())).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,114 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::AppState;
+use crate::telemetry::db_call;
+
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+pub struct HealthReport {
+    pub status: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct ReadinessReport {
+    pub status: &'static str,
+    pub checks: ReadinessChecks,
+}
+
+/// Each check is `"ok"` or a short reason it failed.
+#[derive(Serialize)]
+pub struct ReadinessChecks {
+    pub database: String,
+    pub templates: String,
+    pub shutdown: String,
+}
+
+/// Liveness: the process is up and serving requests.
+pub async fn healthz_handler() -> impl IntoResponse {
+    Json(HealthReport { status: "ok" })
+}
+
+/// Readiness: SurrealDB answers a query, Tera has templates loaded and we
+/// are not shutting down. 503 if any check fails; the status is `degraded`
+/// while the only problem is a database connection being re-established.
+pub async fn readyz_handler(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+    let reconnecting = !app_state.db_health.current().connected;
+    let database = if reconnecting {
+        "reconnecting".to_string()
+    } else {
+        match ping(&app_state).await {
+            Ok(()) => "ok".to_string(),
+            Err(e) => {
+                tracing::warn!(error = %e, "readiness check: database unavailable");
+                "unavailable".to_string()
+            }
+        }
+    };
+    let templates = if app_state.templates.is_empty() {
+        "no templates loaded".to_string()
+    } else {
+        "ok".to_string()
+    };
+    let shutdown = if app_state.shutdown.is_triggered() {
+        "shutting down".to_string()
+    } else {
+        "ok".to_string()
+    };
+
+    let ready = database == "ok" && templates == "ok" && shutdown == "ok";
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let report = ReadinessReport {
+        status: if ready {
+            "ready"
+        } else if reconnecting && templates == "ok" && shutdown == "ok" {
+            "degraded"
+        } else {
+            "unavailable"
+        },
+        checks: ReadinessChecks { database, templates, shutdown },
+    };
+    (status, Json(report))
+}
+
+/// A dropped connection makes queries wait for the driver to reconnect, so
+/// the ping gets a deadline rather than hanging the probe.
+async fn ping(app_state: &AppState) -> Result<(), String> {
+    let query = db_call("ping", "", async { app_state.db.query("RETURN true").await?.check() });
+    match tokio::time::timeout(PING_TIMEOUT, query).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer within {:?}", PING_TIMEOUT)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DbHealth, Link};
+    use crate::handlers::test_state;
+
+    #[tokio::test]
+    async fn unconnected_database_is_not_ready() {
+        // `test_state` uses an unconnected handle, so the query check fails.
+        let response = readyz_handler(State(test_state())).await.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn reconnecting_database_is_degraded() {
+        let (_link, db_health) = DbHealth::new(Link { connected: false, generation: 3 });
+        let state = test_state();
+        let state = Arc::new(AppState { db_health, ..Arc::into_inner(state).unwrap() });
+
+        let response = readyz_handler(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(report["status"], "degraded");
+        assert_eq!(report["checks"]["database"], "reconnecting");
+    }
+}
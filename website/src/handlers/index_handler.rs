@@ -1,14 +1,15 @@
 
 use axum::{
     extract::State,
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    response::Html,
 };
 use serde::Serialize; // Needed for IndexPageData
 use std::sync::Arc;
 use tera::Context;
 
 use crate::AppState;
+use crate::error::AppError;
+use crate::handlers::render;
 
 // Temporary location for IndexPageData
 #[derive(Serialize)]
@@ -19,7 +20,9 @@ pub struct IndexPageData<'a> { // Made pub
     pub show_extra_info: bool,
 }
 
-pub async fn serve_index_page_handler(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+pub async fn serve_index_page_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Html<String>, AppError> {
     let tera = &app_state.templates;
     let mut context = Context::new();
     let page_data = IndexPageData {
@@ -30,15 +33,5 @@ pub async fn serve_index_page_handler(State(app_state): State<Arc<AppState>>) ->
     };
     context.insert("page", &page_data);
 
-    match tera.render("index.html", &context) {
-        Ok(html) => Html(html).into_response(),
-        Err(err) => {
-            eprintln!("Template rendering error: {:?}", err);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response()
-        }
-    }
-}
\ No newline at end of file
+    render(tera, "index.html", &context)
+}
@@ -1,6 +1,51 @@
 pub mod api_handlers;
-pub(crate) mod index_handler;
-pub(crate) mod mario_index_handler;
-pub(crate) mod post_handlers;
-pub(crate) mod rpc_handlers;
-pub(crate) mod counter_handler;
+pub mod dev_handlers;
+pub mod document_handlers;
+pub mod health_handlers;
+pub mod index_handler;
+pub mod mario_index_handler;
+pub mod post_handlers;
+pub mod rpc_handlers;
+pub mod schema_handlers;
+pub mod counter_handler;
+
+use axum::response::Html;
+use tera::Context;
+
+use crate::error::AppError;
+use crate::templates::Templates;
+
+/// Renders a template, turning Tera failures into [`AppError::Template`].
+pub(crate) fn render(tera: &Templates, template: &str, context: &Context) -> Result<Html<String>, AppError> {
+    Ok(Html(tera.render(template, context)?))
+}
+
+/// An `AppState` backed by the in-memory repositories and the real templates,
+/// with an unconnected database handle.
+#[cfg(test)]
+pub(crate) fn test_state() -> std::sync::Arc<crate::AppState> {
+    use std::sync::Arc;
+
+    use crate::config::Config;
+    use crate::documents::Registry;
+    use crate::repository::{MemoryCounterRepository, MemoryDocumentRepository, MemoryPostRepository};
+
+    let config = Config::from_toml(include_str!("../../config/default.toml")).unwrap();
+    let templates = Templates::load(&config.templates.glob()).unwrap();
+    let posts = Arc::new(MemoryPostRepository::default());
+    let documents = Registry::default()
+        .register::<crate::schema::Post>(posts.clone())
+        .register::<crate::schema_v2::Page>(Arc::new(MemoryDocumentRepository::default()))
+        .register::<crate::schema_v2::Author>(Arc::new(MemoryDocumentRepository::default()));
+    Arc::new(crate::AppState {
+        config: Arc::new(config),
+        templates: Arc::new(templates),
+        db: Arc::new(surrealdb::Surreal::init()),
+        db_health: crate::db::DbHealth::default(),
+        posts,
+        counters: Arc::new(MemoryCounterRepository::default()),
+        documents: Arc::new(documents),
+        shutdown: crate::shutdown::Shutdown::default(),
+        live_reload: None,
+    })
+}
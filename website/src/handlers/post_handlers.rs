@@ -1,170 +1,140 @@
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use axum::extract::Path;
+use axum::extract::rejection::JsonRejection;
 use axum::response::Html;
 use tera::Context;
 use crate::AppState;
-use crate::schema::{self, Post, Field, FormType};
-
-#[derive(Deserialize, Serialize,Debug)]
-pub struct CreatePost {
-    pub title: String,
+use crate::error::AppError;
+use crate::handlers::render;
+use crate::outline;
+use crate::schema::{self, Block, Post, PostBlock, Image};
+use crate::validation::{Validate, ValidationErrors};
+
+/// A new block, e.g. `{ "position": 0, "Header": { ... } }`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CreateBlock {
+    /// Index to insert at; the end when missing.
+    pub position: Option<usize>,
+    #[serde(flatten)]
+    pub block: Block,
 }
 
-
-pub async fn serve_admin_page_index_handler(
-    State(app_state): State<Arc<AppState>>
-) -> impl IntoResponse {
-    let tera = &app_state.templates;
-    let db   = &app_state.db;
-
-    // 1) Fetch all posts
-    let posts_res: Result<Vec<Post>, _> = db.select("posts").await;
-    
-
-    match posts_res {
-        Ok(posts) => {
-            // 2) Insert into Tera context
-            let mut context = Context::new();
-            context.insert("posts", &posts);
-
-            // 3) Render the template
-            match tera.render("admin/posts/index.html", &context) {
-                Ok(html) => Html(html).into_response(),
-                Err(err) => {
-                    eprintln!("Template error: {}", err);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Template error: {}", err),
-                    )
-                        .into_response()
-                }
-            }
-        },
-        Err(e) => {
-            // 4) Handle DB error
-            eprintln!("DB error fetching posts: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error loading posts: {}", e),
-            )
-                .into_response()
-        }
-    }
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MoveBlock {
+    pub position: usize,
 }
 
 
-pub async fn serve_admin_page_id_handler(
-    State(app_state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    let tera = &app_state.templates;
-    let db = &app_state.db;
-    let posts_data: Option<Post> = match db.select(("posts", id.clone())).await {
-        Ok(post) => post,
-        Err(e) => {
-            eprintln!("Database error: {:?}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
-        }
-    };
-
-    let Some(post) = posts_data else {
-        return (StatusCode::NOT_FOUND, "Post not found").into_response();
-    };
-
+/// What `admin/posts/[id].html` needs beyond the post: its blocks (the
+/// defaults while it has none), their outline warnings and blank blocks for
+/// the "Add block" forms.
+pub(crate) fn editor_context(post: &Post, context: &mut Context) {
     let page_schema = if post.blocks.is_empty() {
-        schema::default_page_schema()
+        schema::default_page_schema().into_iter().map(PostBlock::from).collect()
     } else {
         post.blocks.clone()
     };
 
-    let mut context = Context::new();
-    context.insert("post", &post);
+    let outline = outline::headings(page_schema.iter().map(|b| &b.block));
+    let outline_warnings: Vec<String> = outline::warnings(&outline).iter().map(ToString::to_string).collect();
+
+    // Empty blocks for the editor's "Add block" forms, by type.
+    let blank_blocks: BTreeMap<&str, Block> =
+        schema::blank_blocks().into_iter().map(|block| (block.type_name(), block)).collect();
+
+    context.insert("post", post);
+    context.insert("definitions", &schema::definitions());
     context.insert("page_schema", &page_schema);
+    context.insert("outline_warnings", &outline_warnings);
+    context.insert("blank_blocks", &blank_blocks);
+    context.insert("blank_image", &Image::default());
+}
 
-    match tera.render("admin/posts/[id].html", &context) {
-        Ok(html) => Html(html).into_response(),
-        Err(err) => {
-            eprintln!("Template rendering error: {:?}", err);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response()
-        }
+/// The HTML of one block as the site renders it, for the editor's preview.
+/// The block comes as the editor holds it, `{ "Quote": { ... } }` with the
+/// text of its inputs, and is parsed by its type's field definitions.
+pub async fn preview_block_handler(
+    State(app_state): State<Arc<AppState>>,
+    payload: Result<Json<Value>, JsonRejection>,
+) -> Result<Html<String>, AppError> {
+    let Json(raw) = payload?;
+    let mut errors = Vec::new();
+    let block = schema::definitions().parse_tagged(&raw, "", &mut errors);
+    if !errors.is_empty() {
+        return Err(ValidationErrors(errors).into());
     }
+    let block: Block = serde_json::from_value(block).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let mut context = Context::new();
+    context.insert("block", &block);
+
+    render(&app_state.templates, "admin/posts/preview.html", &context)
 }
 
-pub async fn create_post_handler(
+pub async fn create_block_handler(
     State(app_state): State<Arc<AppState>>,
-    Json(payload): Json<CreatePost>,
-) -> impl IntoResponse {
-    let title_field = Field {
-        label:      payload.title.clone(),
-        hint:       "".into(),              // or something sensible
-        form_type:  FormType::InputText,    // choose the right variant
-    };
+    Path(id): Path<String>,
+    payload: Result<Json<CreateBlock>, JsonRejection>,
+) -> Result<impl IntoResponse, AppError> {
+    let Json(payload) = payload?;
+    payload.block.validate()?;
+    let block = app_state.posts.create_block(&id, payload.block, payload.position).await?;
+    Ok((StatusCode::CREATED, Json(block)))
+}
 
-    let new_post = Post {
-        id: None,
-        title:  title_field,
-        blocks: vec![],
-    };
-    let db = &app_state.db;
-    let result: Result<Vec<Post>, _> = db.insert("posts").content(new_post).await;
-
-    match result {
-        Ok(mut posts) => {
-            let post = posts.pop().unwrap();
-            (StatusCode::CREATED, Json(post)).into_response()
-        }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": e.to_string()})),
-        )
-            .into_response(),
-    }
+pub async fn update_block_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path((id, block_id)): Path<(String, String)>,
+    payload: Result<Json<Block>, JsonRejection>,
+) -> Result<Json<PostBlock>, AppError> {
+    let Json(payload) = payload?;
+    payload.validate()?;
+    Ok(Json(app_state.posts.update_block(&id, &block_id, payload).await?))
 }
 
-pub async fn get_posts_handler(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
-    let db = &app_state.db;
-    let result: Result<Vec<Post>, _> = db.select("posts").await;
-
-    match result {
-        Ok(posts) => Json(posts).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": e.to_string()})),
-        )
-            .into_response(),
-    }
+pub async fn delete_block_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path((id, block_id)): Path<(String, String)>,
+) -> Result<Json<PostBlock>, AppError> {
+    Ok(Json(app_state.posts.delete_block(&id, &block_id).await?))
 }
 
-pub async fn update_post_handler(
+pub async fn move_block_handler(
     State(app_state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-    Json(payload): Json<Post>,
-) -> impl IntoResponse {
-    let db = &app_state.db;
-
-    let result: Result<Option<Post>, _> = db
-        .update(("posts", id))
-        .content(payload)
-        .await;
-
-    match result {
-        Ok(Some(post)) => (StatusCode::OK, Json(post)).into_response(),
-        Ok(None) => (
-            StatusCode::NOT_FOUND,
-            Json(json!({ "error": "Post not found" })),
-        )
-            .into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": e.to_string() })),
-        )
-            .into_response(),
+    Path((id, block_id)): Path<(String, String)>,
+    payload: Result<Json<MoveBlock>, JsonRejection>,
+) -> Result<Json<Post>, AppError> {
+    let Json(payload) = payload?;
+    Ok(Json(app_state.posts.move_block(&id, &block_id, payload.position).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Extension;
+    use crate::handlers::document_handlers::admin_edit_handler;
+    use crate::handlers::test_state;
+
+    #[tokio::test]
+    async fn admin_page_renders_default_blocks() {
+        let state = test_state();
+        let post = state
+            .posts
+            .create(Post {
+                id: None,
+                title: "Draft".into(),
+                blocks: vec![],
+            })
+            .await
+            .unwrap();
+        let id = post.id.unwrap().id.to_raw();
+
+        let posts = Extension(state.posts.clone() as Arc<dyn crate::repository::DocumentRepository<Post>>);
+        let Html(html) = admin_edit_handler::<Post>(State(state), posts, Path(id)).await.unwrap();
+        assert!(html.contains("<title>Draft</title>"));
+        assert!(html.contains("header-block"));
     }
 }
@@ -1,34 +1,138 @@
 use axum::{
-    extract::{ws::{Message, WebSocket}, State, WebSocketUpgrade},
+    extract::{ws::{close_code, CloseFrame, Message, WebSocket}, State, WebSocketUpgrade},
     response::IntoResponse,
 };
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use std::sync::Arc;
-use surrealdb::Surreal;
-use surrealdb::engine::remote::ws::Client as WsClient;
+use std::time::Duration;
+use surrealdb::Action;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
 use crate::AppState;
-use crate::schema::Post;
+use crate::db::{Backoff, Db, Link};
+use crate::reference::fetch;
+use crate::schema::{Post, PostRecord};
+use crate::telemetry::db_call;
 
 pub async fn rpc_handler(
     State(app_state): State<Arc<AppState>>,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
     let db = app_state.db.clone();
-    ws.on_upgrade(move |socket| handle_ws(socket, db))
+    let link = app_state.db_health.subscribe();
+    let backoff = Backoff::new(
+        app_state.config.database.retry_initial(),
+        app_state.config.database.retry_max(),
+    );
+    let shutdown = app_state.shutdown.token();
+    let tasks = app_state.shutdown.tasks().clone();
+    // Tracked so graceful shutdown waits for the close frame to go out.
+    ws.on_upgrade(move |socket| tasks.track_future(handle_ws(socket, db, link, backoff, shutdown)))
 }
 
-async fn handle_ws(mut socket: WebSocket, db: Arc<Surreal<WsClient>>) {
-    let mut stream = db
-        .select::<Vec<Post>>("posts")
-        .live()
-        .await
-        .unwrap();
+/// Forwards post changes to the socket, as `[action, post]` with the post's
+/// blocks resolved. The live query is re-issued after
+/// every database reconnect, and retried with `backoff` while it cannot be
+/// opened, so clients stay subscribed across an outage.
+async fn handle_ws(
+    mut socket: WebSocket,
+    db: Arc<Db>,
+    mut link: watch::Receiver<Link>,
+    mut backoff: Backoff,
+    shutdown: CancellationToken,
+) {
+    let mut watching = true;
+    'subscribe: loop {
+        let generation = link.borrow_and_update().generation;
+        let live = db_call("live", PostRecord::TABLE, async { db.select::<Vec<PostRecord>>(PostRecord::TABLE).live().await });
+        let mut stream = match live.await {
+            Ok(stream) => {
+                backoff.reset();
+                Some(stream)
+            }
+            Err(e) => {
+                tracing::warn!(table = PostRecord::TABLE, error = %e, "could not start live query");
+                None
+            }
+        };
+        // Only armed while there is no live query.
+        let delay = if stream.is_some() { Duration::ZERO } else { backoff.next_delay() };
+        let retry = tokio::time::sleep(delay);
+        tokio::pin!(retry);
 
-    while let Some(Ok(notification)) = stream.next().await {
-        let txt = serde_json::to_string(&(notification.action, notification.data)).unwrap();
-        if socket.send(Message::Text(txt)).await.is_err() {
-            break;
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => {
+                    close(&mut socket, close_code::AWAY, "server shutting down").await;
+                    break 'subscribe;
+                }
+                changed = link.changed(), if watching => {
+                    if changed.is_err() {
+                        // No monitor (tests): fall back to retrying on the backoff.
+                        watching = false;
+                    } else {
+                        let current = *link.borrow_and_update();
+                        if current.connected && current.generation != generation {
+                            continue 'subscribe;
+                        }
+                    }
+                }
+                _ = &mut retry, if stream.is_none() => continue 'subscribe,
+                notification = next(&mut stream) => match notification {
+                    Some(Ok(notification)) => {
+                        let post = resolve(&db, notification.action, notification.data).await;
+                        let txt = serde_json::to_string(&(notification.action, post)).unwrap();
+                        if socket.send(Message::Text(txt)).await.is_err() {
+                            break 'subscribe;
+                        }
+                    }
+                    Some(Err(e)) => tracing::warn!(error = %e, "live query notification error"),
+                    None => {
+                        tracing::warn!(table = PostRecord::TABLE, "live query ended; resubscribing");
+                        stream = None;
+                        retry.as_mut().reset(Instant::now() + backoff.next_delay());
+                    }
+                },
+                // The client never sends anything meaningful; this only notices it leaving.
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break 'subscribe,
+                    Some(Ok(_)) => {}
+                },
+            }
         }
+        // Dropping the stream at the end of each pass sends KILL for the live query.
     }
-}
\ No newline at end of file
+}
+
+/// The post `record` stands for, read again with its blocks. A deleted post's
+/// blocks are gone with it, so it is sent without them, as is a post that
+/// cannot be read.
+async fn resolve(db: &Db, action: Action, record: PostRecord) -> Post {
+    let key = record.id.as_ref().map(|id| id.id.to_raw());
+    let (Some(key), false) = (key, action == Action::Delete) else {
+        return Post::from(record);
+    };
+    match fetch::<PostRecord>(db, &key).await {
+        Ok(Some(fetched)) => Post::from(fetched),
+        Ok(None) => Post::from(record),
+        Err(e) => {
+            tracing::warn!(table = PostRecord::TABLE, error = %e, "could not resolve a notified post");
+            Post::from(record)
+        }
+    }
+}
+
+/// The next notification, or never while there is no live query.
+async fn next<S: Stream + Unpin>(stream: &mut Option<S>) -> Option<S::Item> {
+    match stream {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn close(socket: &mut WebSocket, code: u16, reason: &'static str) {
+    let frame = CloseFrame { code, reason: reason.into() };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
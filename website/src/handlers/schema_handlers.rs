@@ -0,0 +1,76 @@
+//! The JSON Schema of every registered document type, for editors that
+//! build their forms in the browser. Both routes send an `ETag`, so a
+//! client can revalidate with `If-None-Match` and get a `304` until the
+//! Rust types change.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+use crate::AppState;
+
+/// One registered type, and where its schema is.
+#[derive(Serialize)]
+struct SchemaEntry<'a> {
+    name: &'a str,
+    table: &'a str,
+    label: &'a str,
+    plural: &'a str,
+    schema: String,
+}
+
+/// `GET /api/schema`
+pub async fn list_handler(State(app_state): State<Arc<AppState>>, headers: HeaderMap) -> Result<Response, AppError> {
+    let entries: Vec<SchemaEntry> = app_state
+        .documents
+        .types()
+        .map(|t| SchemaEntry {
+            name: &t.name,
+            table: t.table,
+            label: t.label,
+            plural: t.plural,
+            schema: format!("/api/schema/{}", t.name),
+        })
+        .collect();
+    tagged_json(&headers, &entries)
+}
+
+/// `GET /api/schema/:name`, such as `/api/schema/Page`.
+pub async fn schema_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let schema = app_state.documents.schema(&name).ok_or(AppError::NotFound("Document type"))?;
+    tagged_json(&headers, &schema)
+}
+
+/// `body` as JSON, tagged with the SHA-256 of its bytes; `304 Not Modified`
+/// when `If-None-Match` already has that tag.
+fn tagged_json<T: Serialize>(headers: &HeaderMap, body: &T) -> Result<Response, AppError> {
+    let json = serde_json::to_vec(body).map_err(|e| AppError::Internal(e.to_string()))?;
+    let digest: String = Sha256::digest(&json).iter().map(|b| format!("{:02x}", b)).collect();
+    let etag = format!("\"{}\"", digest);
+
+    let fresh = headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag);
+    let mut response =
+        if fresh { StatusCode::NOT_MODIFIED.into_response() } else { ([(CONTENT_TYPE, "application/json")], json).into_response() };
+
+    let headers = response.headers_mut();
+    headers.insert(ETAG, HeaderValue::from_str(&etag).map_err(|e| AppError::Internal(e.to_string()))?);
+    // Cached, but checked with the server each time.
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    Ok(response)
+}
@@ -0,0 +1,219 @@
+//! Server-side syntax highlighting for `Code` blocks. A small lexer rather
+//! than a grammar engine: it marks comments, strings, numbers and keywords
+//! with `<span class="hl-...">` and escapes everything else, which is all
+//! the site's stylesheet colours.
+
+use std::collections::HashMap;
+
+use tera::Value;
+
+struct Language {
+    line_comment: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+    case_insensitive: bool,
+}
+
+const RUST: Language = Language {
+    line_comment: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false",
+        "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+        "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+    ],
+    case_insensitive: false,
+};
+
+const JAVASCRIPT: Language = Language {
+    line_comment: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    keywords: &[
+        "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete", "do",
+        "else", "export", "extends", "false", "finally", "for", "function", "if", "import", "in", "instanceof",
+        "interface", "let", "new", "null", "of", "return", "static", "super", "switch", "this", "throw", "true",
+        "try", "type", "typeof", "undefined", "var", "void", "while", "yield",
+    ],
+    case_insensitive: false,
+};
+
+const PYTHON: Language = Language {
+    line_comment: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else",
+        "except", "False", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "None",
+        "nonlocal", "not", "or", "pass", "raise", "return", "True", "try", "while", "with", "yield",
+    ],
+    case_insensitive: false,
+};
+
+const SQL: Language = Language {
+    line_comment: &["--"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+    keywords: &[
+        "and", "as", "begin", "by", "commit", "content", "create", "define", "delete", "fetch", "field", "for",
+        "from", "group", "if", "in", "insert", "into", "let", "limit", "live", "merge", "not", "null", "on",
+        "only", "or", "order", "relate", "remove", "return", "schemafull", "select", "set", "table",
+        "transaction", "type", "update", "upsert", "value", "values", "where",
+    ],
+    case_insensitive: true,
+};
+
+const SHELL: Language = Language {
+    line_comment: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in", "local",
+        "return", "then", "while",
+    ],
+    case_insensitive: false,
+};
+
+const DATA: Language = Language {
+    line_comment: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keywords: &["true", "false", "null"],
+    case_insensitive: false,
+};
+
+fn language(name: &str) -> Option<&'static Language> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "javascript" | "js" | "typescript" | "ts" => Some(&JAVASCRIPT),
+        "python" | "py" => Some(&PYTHON),
+        "sql" | "surql" | "surrealql" => Some(&SQL),
+        "shell" | "sh" | "bash" => Some(&SHELL),
+        "json" | "toml" | "yaml" => Some(&DATA),
+        _ => None,
+    }
+}
+
+/// Highlighted, escaped HTML for `code`. Unknown languages are only escaped.
+pub fn highlight(code: &str, language_name: &str) -> String {
+    let Some(lang) = language(language_name) else {
+        return escape(code);
+    };
+    let mut html = String::with_capacity(code.len() * 2);
+    let mut rest = code;
+
+    while let Some(c) = rest.chars().next() {
+        let token_len = if lang.line_comment.iter().any(|m| rest.starts_with(m)) {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            push_span(&mut html, "comment", &rest[..end]);
+            end
+        } else if let Some((open, close)) = lang.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+            let end = rest[open.len()..].find(close).map_or(rest.len(), |at| open.len() + at + close.len());
+            push_span(&mut html, "comment", &rest[..end]);
+            end
+        } else if lang.quotes.contains(&c) {
+            let end = string_end(rest, c);
+            push_span(&mut html, "string", &rest[..end]);
+            end
+        } else if c.is_ascii_digit() {
+            let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_')).unwrap_or(rest.len());
+            push_span(&mut html, "number", &rest[..end]);
+            end
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            let word = &rest[..end];
+            let is_keyword = if lang.case_insensitive {
+                lang.keywords.iter().any(|k| k.eq_ignore_ascii_case(word))
+            } else {
+                lang.keywords.contains(&word)
+            };
+            if is_keyword {
+                push_span(&mut html, "keyword", word);
+            } else {
+                html.push_str(&escape(word));
+            }
+            end
+        } else {
+            html.push_str(&escape(&rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+        rest = &rest[token_len..];
+    }
+    html
+}
+
+/// Length of the string literal at the start of `text`, closing quote
+/// included; an unterminated string runs to the end of the line.
+fn string_end(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' if quote != '`' => return i,
+            c if c == quote => return i + c.len_utf8(),
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn push_span(html: &mut String, class: &str, text: &str) {
+    html.push_str("<span class=\"hl-");
+    html.push_str(class);
+    html.push_str("\">");
+    html.push_str(&escape(text));
+    html.push_str("</span>");
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// `{{ highlight(code=block.Code.code, language=block.Code.language) }}`.
+pub struct HighlightFunction;
+
+impl tera::Function for HighlightFunction {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let code = args
+            .get("code")
+            .and_then(Value::as_str)
+            .ok_or_else(|| tera::Error::msg("highlight() needs a `code` string"))?;
+        let language = args.get("language").and_then(Value::as_str).unwrap_or_default();
+        Ok(Value::String(highlight(code, language)))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_tokens_are_marked() {
+        let html = highlight("fn main() { let s = \"<hi>\"; // done\n}", "rust");
+        assert_eq!(
+            html,
+            "<span class=\"hl-keyword\">fn</span> main() { <span class=\"hl-keyword\">let</span> s = \
+             <span class=\"hl-string\">\"&lt;hi&gt;\"</span>; <span class=\"hl-comment\">// done</span>\n}"
+        );
+    }
+
+    #[test]
+    fn sql_keywords_ignore_case_and_escapes_stay_in_strings() {
+        let html = highlight("SELECT * FROM posts WHERE title = 'it\\'s' LIMIT 10", "surql");
+        assert!(html.starts_with("<span class=\"hl-keyword\">SELECT</span>"));
+        assert!(html.contains("<span class=\"hl-string\">'it\\'s'</span>"));
+        assert!(html.ends_with("<span class=\"hl-number\">10</span>"));
+    }
+
+    #[test]
+    fn unknown_languages_are_only_escaped() {
+        assert_eq!(highlight("<b>if</b>", "brainfuck"), "&lt;b&gt;if&lt;/b&gt;");
+    }
+}
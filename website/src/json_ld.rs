@@ -0,0 +1,131 @@
+//! Structured data for search engines, rendered as
+//! `<script type="application/ld+json">`. See `_scratch/seo.md` for which
+//! fields Google needs. The escaping is shared with every other bit of
+//! JSON a template puts inside a `<script>`.
+
+use std::collections::HashMap;
+
+use chrono::SecondsFormat;
+use serde_json::{json, Map, Value};
+
+use crate::schema::Video;
+
+/// The schema.org `VideoObject` for a video block. Optional fields are left
+/// out rather than sent empty.
+pub fn video_object(video: &Video) -> Value {
+    let mut object = Map::new();
+    object.insert("@context".into(), json!("https://schema.org"));
+    object.insert("@type".into(), json!("VideoObject"));
+    object.insert("name".into(), json!(video.name));
+    object.insert("description".into(), json!(video.description));
+    object.insert("thumbnailUrl".into(), json!([video.thumbnail_url]));
+    object.insert("uploadDate".into(), json!(video.upload_date.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    if let Some(duration) = &video.duration {
+        object.insert("duration".into(), json!(duration));
+    }
+    object.insert("contentUrl".into(), json!(video.content_url));
+    if let Some(embed_url) = &video.embed_url {
+        object.insert("embedUrl".into(), json!(embed_url));
+    }
+    if let Some(publisher) = &video.publisher {
+        let mut organization = json!({ "@type": "Organization", "name": publisher.name });
+        if let Some(logo) = &publisher.logo {
+            organization["logo"] = json!({ "@type": "ImageObject", "url": logo });
+        }
+        object.insert("publisher".into(), organization);
+    }
+    Value::Object(object)
+}
+
+/// The `<script>` element for `data`.
+pub fn script(data: &Value) -> String {
+    format!("<script type=\"application/ld+json\">{}</script>", script_json(data))
+}
+
+/// `data` as JSON that is safe inside any `<script>`: `<`, `>` and `&` are
+/// written as escapes, so no string in it can close the element.
+pub fn script_json(data: &Value) -> String {
+    data.to_string()
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+}
+
+/// `{{ page_schema | json_script }}`: [`script_json`] for data handed to
+/// page scripts in `<script type="application/json">`.
+pub struct JsonScriptFilter;
+
+impl tera::Filter for JsonScriptFilter {
+    fn filter(&self, value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+        Ok(Value::String(script_json(value)))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// `{{ video_json_ld(video=block.Video) }}`.
+pub struct VideoJsonLdFunction;
+
+impl tera::Function for VideoJsonLdFunction {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let value = args
+            .get("video")
+            .ok_or_else(|| tera::Error::msg("video_json_ld() needs a `video` argument"))?;
+        let video: Video = serde_json::from_value(value.clone())
+            .map_err(|e| tera::Error::msg(format!("video_json_ld(): not a video block: {}", e)))?;
+        Ok(Value::String(script(&video_object(&video))))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Publisher;
+
+    #[test]
+    fn video_object_follows_the_seo_notes() {
+        let video = Video {
+            name: "How to Roast Coffee Beans at Home".into(),
+            description: "A 4-minute tutorial.".into(),
+            thumbnail_url: "https://example.com/vid/roast-thumb.jpg".into(),
+            upload_date: "2025-05-22T08:00:00Z".parse().unwrap(),
+            duration: Some("PT4M12S".into()),
+            content_url: "https://cdn.example.com/vid/roast.mp4".into(),
+            embed_url: None,
+            publisher: Some(Publisher { name: "ExampleCoffee".into(), logo: Some("https://example.com/logo.png".into()) }),
+        };
+        assert_eq!(
+            video_object(&video),
+            json!({
+                "@context": "https://schema.org",
+                "@type": "VideoObject",
+                "name": "How to Roast Coffee Beans at Home",
+                "description": "A 4-minute tutorial.",
+                "thumbnailUrl": ["https://example.com/vid/roast-thumb.jpg"],
+                "uploadDate": "2025-05-22T08:00:00Z",
+                "duration": "PT4M12S",
+                "contentUrl": "https://cdn.example.com/vid/roast.mp4",
+                "publisher": {
+                    "@type": "Organization",
+                    "name": "ExampleCoffee",
+                    "logo": { "@type": "ImageObject", "url": "https://example.com/logo.png" }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn strings_cannot_close_the_script() {
+        let html = script(&json!({ "name": "</script><b>&" }));
+        assert_eq!(
+            html,
+            "<script type=\"application/ld+json\">{\"name\":\"\\u003c/script\\u003e\\u003cb\\u003e\\u0026\"}</script>"
+        );
+    }
+}
@@ -1,2 +1,56 @@
+// Lets `#[derive(Schema)]` expand to `::website::surql::...` inside this crate too.
+extern crate self as website;
+
+pub mod app;
+pub mod config;
+pub mod db;
+pub mod dev;
+pub mod documents;
+pub mod error;
+pub mod fields;
+pub mod form_input;
+pub mod handlers;
+pub mod highlight;
+pub mod json_ld;
+pub mod migrations;
+pub mod outline;
+pub mod reference;
+pub mod repository;
+pub mod rich_text;
+pub mod schema;
+pub mod schema_form;
 pub mod schema_v2;
-pub mod macro_test; 
\ No newline at end of file
+pub mod shutdown;
+pub mod telemetry;
+pub mod surql;
+pub mod templates;
+pub mod validation;
+pub mod widgets;
+pub mod macro_test;
+
+use std::sync::Arc;
+
+pub use crate::app::app;
+
+use crate::config::Config;
+use crate::db::{Db, DbHealth};
+use crate::dev::LiveReload;
+use crate::documents::Registry;
+use crate::repository::{CounterRepository, PostRepository};
+use crate::shutdown::Shutdown;
+use crate::templates::Templates;
+
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub templates: Arc<Templates>,
+    /// Raw handle, for what the repositories don't cover (live queries).
+    pub db: Arc<Db>,
+    pub db_health: DbHealth,
+    pub posts: Arc<dyn PostRepository>,
+    pub counters: Arc<dyn CounterRepository>,
+    /// Every document type with its routes, posts included.
+    pub documents: Arc<Registry>,
+    pub shutdown: Shutdown,
+    /// Set in dev mode only.
+    pub live_reload: Option<LiveReload>,
+}
@@ -1,6 +1,5 @@
 #[cfg(test)]
 mod html_snapshots {
-    use super::*;
     use insta::assert_snapshot;
     use tera::{Context, Tera};
     use crate::schema_v2::{Page, RobotsMeta, SeoMetadata};
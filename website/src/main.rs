@@ -1,132 +1,272 @@
-mod handlers;
-mod schema;
-mod schema_v2;
-
-use axum::{
-    routing::{get, post},
-    Router,
-};
-use std::net::SocketAddr;
-use std::path::Path;
 use std::sync::Arc;
-use tera::Tera;
-use surrealdb::engine::remote::ws::{Client as WsClient, Ws};
-use surrealdb::{opt::auth::Root, Surreal};
 use tokio::net::TcpListener;
-use tower_http::services::ServeDir;
 
-pub struct AppState {
-    pub templates: Arc<Tera>,
-    pub db: Arc<Surreal<WsClient>>,
+use website::config::Config;
+use website::db::{Db, DbHealth};
+use website::migrations::{self, Mode};
+use website::repository::{SurrealCounterRepository, SurrealPostRepository};
+use website::shutdown::{self, Shutdown};
+use website::dev::LiveReload;
+use website::documents;
+use website::surql::{self, Stale, StaleField};
+use website::telemetry;
+use website::templates::Templates;
+use website::{app, db, AppState};
+
+/// `website` serves; `website migrate [--dry-run | --prune-fields]` and
+/// `website migrate status` run or inspect migrations against the configured
+/// database and exit; `website schema` prints the SurrealQL derived from
+/// `schema.rs`. Only `--prune-fields` removes fields `schema.rs` no longer has.
+enum Command {
+    Serve,
+    Migrate(Mode, Stale),
+    MigrationStatus,
+    Schema,
+}
+
+impl Command {
+    fn from_args() -> Result<Self, String> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            [] => Ok(Command::Serve),
+            ["migrate"] => Ok(Command::Migrate(Mode::Apply, Stale::Keep)),
+            ["migrate", "--dry-run"] => Ok(Command::Migrate(Mode::DryRun, Stale::Keep)),
+            ["migrate", "--prune-fields"] => Ok(Command::Migrate(Mode::Apply, Stale::Remove)),
+            ["migrate", "status"] => Ok(Command::MigrationStatus),
+            ["schema"] => Ok(Command::Schema),
+            other => Err(format!(
+                "unknown arguments {:?}; expected none, `migrate`, `migrate --dry-run`, `migrate --prune-fields`, \
+                 `migrate status` or `schema`",
+                other
+            )),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    let tera_instance = match Tera::new("website/src/templates/**/*.html") {
-        Ok(t) => {
-            println!("Tera templates loaded successfully.");
-            t
+    let command = match Command::from_args() {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("FATAL: {}", e);
+            ::std::process::exit(2);
         }
+    };
+    if let Command::Schema = command {
+        println!("{}", surql::statements().join("\n"));
+        return;
+    }
+
+    let config = match Config::load() {
+        Ok(c) => c,
         Err(e) => {
-            eprintln!("FATAL: Parsing error(s) on template initialization: {}", e);
+            // No subscriber yet: the log settings are part of the configuration.
+            eprintln!("FATAL: {}", e);
             ::std::process::exit(1);
         }
     };
-    let shared_tera = Arc::new(tera_instance);
+    telemetry::init(&config.log);
+    tracing::info!("configuration loaded");
+    let shared_config = Arc::new(config);
+    let db_config = &shared_config.database;
 
-    println!("Attempting to connect to SurrealDB at ws://127.0.0.1:8000...");
-    let db = match Surreal::new::<Ws>("127.0.0.1:8000").await {
+    let engine = match db_config.engine() {
+        Ok(engine) => engine,
+        Err(e) => {
+            tracing::error!("FATAL: {}", e);
+            ::std::process::exit(1);
+        }
+    };
+    tracing::info!(
+        endpoint = %engine.endpoint(),
+        namespace = %db_config.namespace,
+        database = %db_config.database,
+        "connecting to SurrealDB"
+    );
+    let db = match db::connect_with_retry(db_config, &engine).await {
         Ok(db_instance) => {
-            println!("Successfully initiated SurrealDB connection.");
+            tracing::info!("connected to SurrealDB");
             db_instance
         }
         Err(e) => {
-            eprintln!("FATAL: Could not connect to SurrealDB: {:?}", e);
+            tracing::error!(
+                attempts = db_config.connect_attempts,
+                error = %e,
+                "FATAL: could not connect to SurrealDB"
+            );
             ::std::process::exit(1);
         }
     };
 
-    println!("Attempting to sign in to SurrealDB...");
-    if let Err(e) = db
-        .signin(Root {
-            username: "root",
-            password: "root",
-        })
-        .await
-    {
-        eprintln!("FATAL: Could not sign in to SurrealDB: {:?}", e);
-        ::std::process::exit(1);
+    match command {
+        Command::Serve | Command::Schema => {}
+        Command::Migrate(mode, stale) => {
+            run_migrations(&db, mode, stale).await;
+            return;
+        }
+        Command::MigrationStatus => {
+            print_migration_status(&db).await;
+            return;
+        }
     }
-    println!("Successfully signed in to SurrealDB.");
-
-    println!("Attempting to use namespace 'test' and database 'test'...");
-    if let Err(e) = db.use_ns("test").use_db("test").await {
-        eprintln!(
-            "FATAL: Could not use namespace/database in SurrealDB: {:?}",
-            e
-        );
-        ::std::process::exit(1);
+    if db_config.migrate_on_startup {
+        run_migrations(&db, Mode::Apply, Stale::Keep).await;
     }
-    println!("Successfully set SurrealDB namespace and database.");
 
+    let templates = match Templates::load(&shared_config.templates.glob()) {
+        Ok(t) => {
+            tracing::info!("templates loaded");
+            t
+        }
+        Err(e) => {
+            tracing::error!(error = ?e, "FATAL: could not parse templates");
+            ::std::process::exit(1);
+        }
+    };
+    let shared_tera = Arc::new(templates);
+
+    let shutdown = Shutdown::default();
+    let db_health = DbHealth::spawn(db.clone(), db_config.health_check_interval(), shutdown.token());
     let shared_db = Arc::new(db);
+    let public_dir = shared_config.server.public_dir();
+
+    let live_reload = if shared_config.dev.live_reload {
+        match LiveReload::watch(
+            shared_tera.clone(),
+            shared_config.templates.dir(),
+            public_dir.clone(),
+            shutdown.token(),
+        ) {
+            Ok(live_reload) => {
+                tracing::info!(?public_dir, "dev mode: watching templates and public files");
+                Some(live_reload)
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "FATAL: could not watch files for live reload");
+                ::std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
 
+    let posts = Arc::new(SurrealPostRepository::new(shared_db.clone()));
+    let documents = documents::registry(&shared_db, posts.clone());
     let app_state = Arc::new(AppState {
+        config: shared_config.clone(),
         templates: shared_tera.clone(),
+        posts,
+        counters: Arc::new(SurrealCounterRepository::new(shared_db.clone())),
+        documents: Arc::new(documents),
         db: shared_db,
+        db_health,
+        shutdown: shutdown.clone(),
+        live_reload,
     });
-    println!("AppState created successfully.");
-    let public_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("public");
-    println!("Public Dir: {:?}", public_dir);
-    let static_files_service = ServeDir::new(public_dir).append_index_html_on_directories(false);
-
-    let app = Router::new()
-        .route(
-            "/admin/posts/",
-            get(handlers::post_handlers::serve_admin_page_index_handler)
-        )
-        .route(
-            "/admin/posts/:id",
-            get(handlers::post_handlers::serve_admin_page_id_handler)
-        )
-        .route(
-            "/",
-            get(handlers::index_handler::serve_index_page_handler),
-        )
-        .route("/mario", get(handlers::mario_index_handler::serve_index_page_handler))
-        .route(
-            "/api/hello",
-            get(handlers::api_handlers::hello_json_api_handler),
-        )
-        .route(
-            "/api/posts",
-            post(handlers::post_handlers::create_post_handler)
-                .get(handlers::post_handlers::get_posts_handler),
-        )
-        .route(
-            "/api/posts/:id",
-            post(handlers::post_handlers::update_post_handler),
-        )
-        .route("/counter", get(handlers::counter_handler::page_handler))
-        .route("/api/counter/:id", post(handlers::counter_handler::create_handler))
-        .route("/rpc", get(handlers::rpc_handlers::rpc_handler))
-        .fallback_service(static_files_service)
-        .with_state(app_state);
-    println!("Axum router configured.");
-
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
-    println!("Server listening on http://{}", addr);
+    tracing::debug!(?public_dir, "serving static files");
+    let router = app(app_state);
+
+    let addr = shared_config.server.socket_addr();
 
     let listener = match TcpListener::bind(addr).await {
         Ok(l) => l,
         Err(e) => {
-            eprintln!("FATAL: Could not bind to address {}: {:?}", addr, e);
+            tracing::error!(%addr, error = %e, "FATAL: could not bind");
             ::std::process::exit(1);
         }
     };
+    tracing::info!("listening on http://{}", addr);
 
-    if let Err(e) = axum::serve(listener, app.into_make_service()).await {
-        eprintln!("FATAL: Server error: {:?}", e);
+    let signal_shutdown = shutdown.clone();
+    let serve = axum::serve(listener, router.into_make_service()).with_graceful_shutdown(async move {
+        shutdown::signal().await;
+        tracing::info!("shutdown signal received; closing WebSockets and draining connections");
+        signal_shutdown.trigger();
+    });
+    if let Err(e) = serve.await {
+        tracing::error!(error = %e, "FATAL: server error");
         ::std::process::exit(1);
     }
-}
\ No newline at end of file
+
+    let grace = shared_config.server.shutdown_grace();
+    if shutdown.drain(grace).await {
+        tracing::info!("all WebSocket clients closed; bye");
+    } else {
+        tracing::warn!(?grace, "gave up waiting for WebSocket clients");
+    }
+}
+
+async fn run_migrations(db: &Db, mode: Mode, stale: Stale) {
+    match migrations::run(db, mode).await {
+        Ok(ran) if ran.is_empty() => tracing::info!("migrations: database is up to date"),
+        Ok(ran) => {
+            for migration in ran {
+                match mode {
+                    Mode::Apply => tracing::info!(migration = migration.name, "applied migration"),
+                    Mode::DryRun => {
+                        println!("Would apply migration {}:\n{}", migration.name, migration.sql)
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("FATAL: {}", e);
+            ::std::process::exit(1);
+        }
+    }
+
+    // The derived tables follow `schema.rs`, after the numbered migrations.
+    match mode {
+        Mode::Apply => match surql::apply(db, stale).await {
+            Ok(fields) => {
+                for field in fields {
+                    match stale {
+                        Stale::Remove => tracing::warn!(%field, "removed field no longer in schema.rs"),
+                        Stale::Keep => tracing::warn!(
+                            %field,
+                            "field no longer in schema.rs is still defined; `website migrate --prune-fields` removes it"
+                        ),
+                    }
+                }
+                tracing::info!("derived schema applied");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "FATAL: could not apply the derived schema");
+                ::std::process::exit(1);
+            }
+        },
+        Mode::DryRun => {
+            println!("Would apply the derived schema:\n{}", surql::statements().join("\n"));
+            match surql::stale_fields(db).await {
+                Ok(fields) if fields.is_empty() => {}
+                Ok(fields) => {
+                    let removals: Vec<String> = fields.iter().map(StaleField::remove_statement).collect();
+                    println!("Fields no longer in schema.rs; `--prune-fields` would run:\n{}", removals.join("\n"));
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "FATAL: could not read the defined fields");
+                    ::std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+async fn print_migration_status(db: &Db) {
+    match migrations::status(db).await {
+        Ok(statuses) => {
+            for status in statuses {
+                let state = match &status.applied {
+                    Some(applied) => format!("applied {}", applied.applied_at.to_raw()),
+                    None => "pending".to_string(),
+                };
+                println!("{:<28} {}", status.migration.name, state);
+            }
+        }
+        Err(e) => {
+            tracing::error!("FATAL: {}", e);
+            ::std::process::exit(1);
+        }
+    }
+}
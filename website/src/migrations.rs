@@ -0,0 +1,307 @@
+//! Numbered SurrealQL migrations under `website/migrations/`, applied in
+//! order and recorded in the `_migrations` table.
+//!
+//! To add one, create `migrations/NNNN_description.surql` and append it to
+//! [`MIGRATIONS`]. Never edit a migration that has shipped: the checksum
+//! check refuses to run against a database that applied a different version.
+//!
+//! Tables with a Rust type are defined by [`crate::surql`] instead; numbered
+//! migrations are for data changes and tables that have no type yet.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use surrealdb::sql::Datetime;
+
+use crate::db::Db;
+use crate::telemetry::db_call;
+
+pub const TABLE: &str = "_migrations";
+
+pub struct Migration {
+    pub version: u32,
+    /// File stem, e.g. `0001_create_posts`.
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            sql: include_str!(concat!("../migrations/", $name, ".surql")),
+        }
+    };
+}
+
+/// Every migration, in the order it must run.
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_create_posts"),
+    migration!(2, "0002_create_blocks"),
+    migration!(3, "0003_create_counter"),
+    migration!(4, "0004_blocks_as_records"),
+    migration!(5, "0005_header_element"),
+    migration!(6, "0006_field_values"),
+];
+
+impl Migration {
+    pub fn checksum(&self) -> String {
+        Sha256::digest(self.sql.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// A row of the `_migrations` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: Datetime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Apply,
+    /// Report what would run without touching the database.
+    DryRun,
+}
+
+pub struct MigrationStatus<'a> {
+    pub migration: &'a Migration,
+    pub applied: Option<AppliedMigration>,
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(Box<surrealdb::Error>),
+    /// An applied migration no longer matches the file shipped in this build.
+    ChecksumMismatch { name: String },
+    /// The database has applied a migration this build does not know about.
+    Unknown { name: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "migration failed: {}", e),
+            MigrationError::ChecksumMismatch { name } => {
+                write!(f, "migration {} was edited after it was applied", name)
+            }
+            MigrationError::Unknown { name } => {
+                write!(f, "database has migration {} which this build does not ship", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<surrealdb::Error> for MigrationError {
+    fn from(e: surrealdb::Error) -> Self {
+        MigrationError::Database(Box::new(e))
+    }
+}
+
+/// Reads `_migrations`, ordered by version.
+pub async fn applied(db: &Db) -> Result<Vec<AppliedMigration>, MigrationError> {
+    let mut response = db_call("query", TABLE, async {
+        db.query("SELECT version, name, checksum, applied_at FROM type::table($table) ORDER BY version")
+            .bind(("table", TABLE))
+            .await
+    })
+    .await?;
+    Ok(response.take(0)?)
+}
+
+/// Pairs every known migration with its `_migrations` row, if any, and
+/// checks that the two agree.
+pub async fn status(db: &Db) -> Result<Vec<MigrationStatus<'static>>, MigrationError> {
+    let mut applied = applied(db).await?;
+
+    if let Some(unknown) = applied
+        .iter()
+        .find(|a| !MIGRATIONS.iter().any(|m| m.version == a.version))
+    {
+        return Err(MigrationError::Unknown { name: unknown.name.clone() });
+    }
+
+    let mut statuses = Vec::with_capacity(MIGRATIONS.len());
+    for migration in MIGRATIONS {
+        let row = applied
+            .iter()
+            .position(|a| a.version == migration.version)
+            .map(|i| applied.swap_remove(i));
+        if let Some(row) = &row
+            && row.checksum != migration.checksum()
+        {
+            return Err(MigrationError::ChecksumMismatch { name: migration.name.to_string() });
+        }
+        statuses.push(MigrationStatus { migration, applied: row });
+    }
+    Ok(statuses)
+}
+
+/// Runs every pending migration, each in its own transaction together with
+/// its `_migrations` row. Returns the migrations that ran (or would run, for
+/// [`Mode::DryRun`]).
+pub async fn run(db: &Db, mode: Mode) -> Result<Vec<&'static Migration>, MigrationError> {
+    let pending: Vec<&'static Migration> = status(db)
+        .await?
+        .into_iter()
+        .filter(|s| s.applied.is_none())
+        .map(|s| s.migration)
+        .collect();
+
+    if mode == Mode::DryRun {
+        return Ok(pending);
+    }
+
+    for migration in &pending {
+        let sql = format!(
+            "BEGIN TRANSACTION;\n{};\nCREATE type::thing($table, $version) CONTENT {{ version: $version, name: $name, checksum: $checksum, applied_at: time::now() }};\nCOMMIT TRANSACTION;",
+            migration.sql.trim_end().trim_end_matches(';')
+        );
+        db_call("migrate", migration.name, async {
+            db.query(sql)
+                .bind(("table", TABLE))
+                .bind(("version", migration.version))
+                .bind(("name", migration.name))
+                .bind(("checksum", migration.checksum()))
+                .await?
+                .check()
+        })
+        .await?;
+    }
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any;
+
+    async fn memory_db() -> Db {
+        let db = any::connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db
+    }
+
+    #[test]
+    fn versions_are_unique_and_ascending() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert!(versions.windows(2).all(|w| w[0] < w[1]), "{:?}", versions);
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_without_applying() {
+        let db = memory_db().await;
+        let pending = run(&db, Mode::DryRun).await.unwrap();
+        assert_eq!(pending.len(), MIGRATIONS.len());
+        assert!(applied(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn apply_is_idempotent() {
+        let db = memory_db().await;
+        let first = run(&db, Mode::Apply).await.unwrap();
+        assert_eq!(first.len(), MIGRATIONS.len());
+
+        let second = run(&db, Mode::Apply).await.unwrap();
+        assert!(second.is_empty());
+
+        let statuses = status(&db).await.unwrap();
+        assert!(statuses.iter().all(|s| s.applied.is_some()));
+    }
+
+    #[tokio::test]
+    async fn old_posts_upgrade_to_the_current_model() {
+        let db = memory_db().await;
+        for migration in &MIGRATIONS[..3] {
+            db.query(migration.sql).await.unwrap().check().unwrap();
+        }
+        db.query(
+            "CREATE posts:old CONTENT { title: { label: 'Old', hint: '', form_type: 'InputText' }, blocks: $blocks }",
+        )
+        .bind((
+            "blocks",
+            serde_json::json!([
+                { "Header": { "content": { "label": "Header", "hint": "", "form_type": "InputArea" } } },
+                { "Footer": { "copyright": { "label": "Copyright", "hint": "", "form_type": "InputText" } } },
+            ]),
+        ))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        for migration in &MIGRATIONS[3..] {
+            db.query(migration.sql).await.unwrap().check().unwrap();
+        }
+        crate::surql::apply(&db, crate::surql::Stale::Keep).await.unwrap();
+
+        let post: crate::schema::Post = crate::reference::fetch::<crate::schema::PostRecord>(&db, "old")
+            .await
+            .unwrap()
+            .unwrap()
+            .into();
+        assert_eq!(post.title, "Old");
+        assert_eq!(post.blocks.len(), 2);
+        assert!(matches!(&post.blocks[0].block, crate::schema::Block::Header(h) if h.content == "Header"));
+        assert!(matches!(&post.blocks[1].block, crate::schema::Block::Footer(f) if f.copyright == "Copyright"));
+
+        let bad = db
+            .query("CREATE posts CONTENT { title: 'x', blocks: [{ Header: {} }] }")
+            .await
+            .unwrap()
+            .check();
+        assert!(bad.is_err(), "blocks must be links now");
+    }
+
+    #[tokio::test]
+    async fn old_headers_become_h2() {
+        let db = memory_db().await;
+        for migration in &MIGRATIONS[..4] {
+            db.query(migration.sql).await.unwrap().check().unwrap();
+        }
+        db.query(
+            "CREATE blocks:old SET Header = { content: { label: 'Old', hint: '', form_type: 'InputArea' } };
+             CREATE blocks:footer SET Footer = { copyright: { label: 'c', hint: '', form_type: 'InputText' } };",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        db.query(MIGRATIONS[4].sql).await.unwrap().check().unwrap();
+        crate::surql::apply(&db, crate::surql::Stale::Keep).await.unwrap();
+
+        let mut response = db
+            .query("SELECT VALUE Header.element FROM ONLY blocks:old; SELECT VALUE Header FROM ONLY blocks:footer;")
+            .await
+            .unwrap();
+        let element: Option<String> = response.take(0).unwrap();
+        assert_eq!(element.as_deref(), Some("h2"));
+        let footer_header: Option<serde_json::Value> = response.take(1).unwrap();
+        assert_eq!(footer_header, None, "other blocks get no Header");
+    }
+
+    #[tokio::test]
+    async fn edited_migration_is_refused() {
+        let db = memory_db().await;
+        run(&db, Mode::Apply).await.unwrap();
+        db.query("UPDATE type::thing($table, 1) SET checksum = 'tampered'")
+            .bind(("table", TABLE))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let err = run(&db, Mode::Apply).await.err().expect("checksum mismatch");
+        assert!(matches!(err, MigrationError::ChecksumMismatch { name } if name == "0001_create_posts"));
+    }
+}
@@ -0,0 +1,129 @@
+//! The heading outline of a document, from `Header` blocks and rich text
+//! headings, and the problems with it that hurt screen-reader navigation
+//! and search: skipped levels and more than one `h1`.
+
+use std::fmt;
+
+use crate::schema::Block;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// 1 for `h1` to 6 for `h6`.
+    pub level: u8,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutlineWarning {
+    /// `heading` is more than one level below the heading before it.
+    SkippedLevel { from: u8, heading: Heading },
+    /// Every `h1` when there is more than one.
+    MultipleH1 { headings: Vec<String> },
+}
+
+impl fmt::Display for OutlineWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutlineWarning::SkippedLevel { from, heading } => write!(
+                f,
+                "\"{}\" is an h{} but the heading before it is an h{}",
+                heading.text, heading.level, from
+            ),
+            OutlineWarning::MultipleH1 { headings } => {
+                let quoted: Vec<String> = headings.iter().map(|text| format!("\"{}\"", text)).collect();
+                write!(f, "{} headings are h1 ({}); a page should have one", headings.len(), quoted.join(", "))
+            }
+        }
+    }
+}
+
+/// The headings of `blocks`, in document order. `p` headers are not
+/// headings.
+pub fn headings<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    for block in blocks {
+        match block {
+            Block::Header(header) => {
+                if let Some(level) = header.element.level() {
+                    headings.push(Heading { level, text: header.content.clone() });
+                }
+            }
+            Block::RichText(text) => {
+                for text_block in &text.body {
+                    if let Some(level) = text_block.style.heading_level() {
+                        let text = text_block.children.iter().map(|span| span.text.as_str()).collect();
+                        headings.push(Heading { level, text });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+/// What is wrong with an outline. The first heading may be at any level;
+/// after that a heading may go at most one level deeper than the last.
+pub fn warnings(headings: &[Heading]) -> Vec<OutlineWarning> {
+    let mut warnings: Vec<OutlineWarning> = headings
+        .windows(2)
+        .filter(|pair| pair[1].level > pair[0].level + 1)
+        .map(|pair| OutlineWarning::SkippedLevel { from: pair[0].level, heading: pair[1].clone() })
+        .collect();
+
+    let h1s: Vec<String> = headings.iter().filter(|h| h.level == 1).map(|h| h.text.clone()).collect();
+    if h1s.len() > 1 {
+        warnings.push(OutlineWarning::MultipleH1 { headings: h1s });
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rich_text::{RichText, Span, TextBlock, TextStyle};
+    use crate::schema::{HTMLElement, Header};
+
+    fn header(text: &str, element: HTMLElement) -> Block {
+        Block::Header(Header { content: text.into(), element })
+    }
+
+    #[test]
+    fn headers_and_rich_text_headings_form_the_outline() {
+        let text = RichText {
+            body: [(TextStyle::H2, "Intro"), (TextStyle::Normal, "Body"), (TextStyle::H4, "Detail")]
+                .into_iter()
+                .map(|(style, text)| TextBlock {
+                    style,
+                    list_item: None,
+                    children: vec![Span { text: text.into(), marks: vec![] }],
+                    mark_defs: vec![],
+                })
+                .collect(),
+        };
+        let blocks = [
+            header("Title", HTMLElement::H1),
+            header("Kicker", HTMLElement::P),
+            Block::RichText(text),
+            header("Also a title", HTMLElement::H1),
+        ];
+        let outline = headings(&blocks);
+        let levels: Vec<u8> = outline.iter().map(|h| h.level).collect();
+        assert_eq!(levels, [1, 2, 4, 1]);
+
+        let messages: Vec<String> = warnings(&outline).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "\"Detail\" is an h4 but the heading before it is an h2",
+                "2 headings are h1 (\"Title\", \"Also a title\"); a page should have one",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_document_may_start_below_h1() {
+        let outline = headings(&[header("Section", HTMLElement::H2), header("Sub", HTMLElement::H3)]);
+        assert!(warnings(&outline).is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+//! Typed record links: a `Reference<Author>` field is stored as a
+//! `record<authors>` link and can be resolved with `FETCH`.
+//!
+//! A reference always writes back as the bare link, so saving a document
+//! that was read with its references fetched never embeds the targets.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Unexpected, Visitor,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use surrealdb::sql::Id;
+
+use crate::db::Db;
+use crate::surql::{Schema, TableSchema, Thing};
+use crate::telemetry::db_call;
+
+/// A link to a record of `T`'s table, plus the record itself once fetched.
+#[derive(Debug, Clone)]
+pub struct Reference<T> {
+    id: Thing,
+    value: Option<Box<T>>,
+}
+
+impl<T: TableSchema> Reference<T> {
+    /// A link to `<T::TABLE>:<key>`.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { id: Thing::from((T::TABLE, Id::String(key.into()))), value: None }
+    }
+
+    /// A link to a stored record, keeping it as the resolved value.
+    /// Returns `None` if `record` has no id yet.
+    pub fn to(record: T) -> Option<Self> {
+        let id = record.record_id()?.clone();
+        Some(Self { id, value: Some(Box::new(record)) })
+    }
+
+    /// A link to an existing record id; fails if it points at another table.
+    pub fn from_thing(id: Thing) -> Result<Self, String> {
+        if id.tb != T::TABLE {
+            return Err(format!("expected a record of `{}`, got `{}`", T::TABLE, id));
+        }
+        Ok(Self { id, value: None })
+    }
+
+    pub fn id(&self) -> &Thing {
+        &self.id
+    }
+
+    /// The target, if the link was read with `FETCH` or [`Reference::load`]ed.
+    pub fn get(&self) -> Option<&T> {
+        self.value.as_deref()
+    }
+
+    pub fn into_inner(self) -> Option<T> {
+        self.value.map(|v| *v)
+    }
+
+    /// Reads the target record; `None` if it has been deleted.
+    pub async fn load(&mut self, db: &Db) -> Result<Option<&T>, surrealdb::Error>
+    where
+        T: DeserializeOwned,
+    {
+        let id = self.id.clone();
+        let record: Option<T> = db_call("select", T::TABLE, async {
+            db.query("SELECT * FROM ONLY $id").bind(("id", id)).await?.take(0)
+        })
+        .await?;
+        self.value = record.map(Box::new);
+        Ok(self.get())
+    }
+}
+
+impl<T> PartialEq for Reference<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> fmt::Display for Reference<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.id.fmt(f)
+    }
+}
+
+impl<T> Serialize for Reference<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.id.serialize(serializer)
+    }
+}
+
+impl<'de, T: TableSchema + DeserializeOwned> Deserialize<'de> for Reference<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ReferenceVisitor(PhantomData))
+    }
+}
+
+/// A stored reference is the link itself (`{ tb, id }`), a `table:key`
+/// string (from JSON and forms), or the whole record after `FETCH`. The two
+/// maps are told apart by their first key; SurrealDB hands ids over as
+/// enums, which serde's `untagged` cannot buffer.
+struct ReferenceVisitor<T>(PhantomData<T>);
+
+/// The keys of a stored link, in SurrealDB's order.
+const LINK_FIELDS: &[&str] = &["tb", "id"];
+
+impl<'de, T: TableSchema + DeserializeOwned> Visitor<'de> for ReferenceVisitor<T> {
+    type Value = Reference<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a record link or record of `{}`", T::TABLE)
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        let (table, key) = text.split_once(':').unwrap_or((T::TABLE, text));
+        if table.is_empty() || key.is_empty() {
+            return Err(E::invalid_value(Unexpected::Str(text), &self));
+        }
+        Reference::from_thing(Thing::from((table, Id::String(key.to_string())))).map_err(E::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let Some(first) = map.next_key::<String>()? else {
+            return Err(de::Error::invalid_length(0, &self));
+        };
+        if first == "tb" {
+            let tb: String = map.next_value()?;
+            let id: Id = match map.next_key::<String>()?.as_deref() {
+                Some("id") => map.next_value()?,
+                Some(other) => return Err(de::Error::unknown_field(other, LINK_FIELDS)),
+                None => return Err(de::Error::missing_field("id")),
+            };
+            if let Some(other) = map.next_key::<String>()? {
+                return Err(de::Error::unknown_field(&other, LINK_FIELDS));
+            }
+            return Reference::from_thing(Thing::from((tb, id))).map_err(de::Error::custom);
+        }
+
+        let record = T::deserialize(MapAccessDeserializer::new(Replay { first: Some(first), map }))?;
+        let id = record
+            .record_id()
+            .cloned()
+            .ok_or_else(|| de::Error::custom(format!("a fetched `{}` record has no id", T::TABLE)))?;
+        let mut reference = Reference::from_thing(id).map_err(de::Error::custom)?;
+        reference.value = Some(Box::new(record));
+        Ok(reference)
+    }
+}
+
+/// Hands the already-read first key back before the rest of the map.
+struct Replay<A> {
+    first: Option<String>,
+    map: A,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for Replay<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error> {
+        match self.first.take() {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
+
+impl<T: TableSchema> Schema for Reference<T> {
+    fn surql_type() -> String {
+        format!("record<{}>", T::TABLE)
+    }
+
+    fn references(path: &str, out: &mut Vec<String>) {
+        out.push(path.to_string());
+    }
+}
+
+/// Reads one `D` with every reference in it resolved.
+pub async fn fetch<D>(db: &Db, key: &str) -> Result<Option<D>, surrealdb::Error>
+where
+    D: TableSchema + DeserializeOwned,
+{
+    let sql = format!("SELECT * FROM ONLY type::thing($tb, $key){};", fetch_clause::<D>());
+    db_call("select", D::TABLE, async {
+        db.query(sql).bind(("tb", D::TABLE)).bind(("key", key.to_string())).await?.take(0)
+    })
+    .await
+}
+
+/// Reads every `D` with every reference in it resolved.
+pub async fn fetch_all<D>(db: &Db) -> Result<Vec<D>, surrealdb::Error>
+where
+    D: TableSchema + DeserializeOwned,
+{
+    let sql = format!("SELECT * FROM type::table($tb){};", fetch_clause::<D>());
+    db_call("select", D::TABLE, async { db.query(sql).bind(("tb", D::TABLE)).await?.take(0) }).await
+}
+
+fn fetch_clause<D: TableSchema>() -> String {
+    let paths = D::reference_paths();
+    if paths.is_empty() {
+        String::new()
+    } else {
+        format!(" FETCH {}", paths.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any;
+
+    use crate::surql::Schema;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, Schema)]
+    #[schema(table = "authors")]
+    struct Author {
+        id: Option<Thing>,
+        name: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, Schema)]
+    #[schema(table = "books")]
+    struct Book {
+        id: Option<Thing>,
+        title: String,
+        author: Reference<Author>,
+        editors: Vec<Reference<Author>>,
+    }
+
+    async fn db() -> Db {
+        let db = any::connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let sql = [Author::define_table(), Book::define_table()].concat().join("\n");
+        db.query(sql).await.unwrap().check().unwrap();
+        db
+    }
+
+    #[test]
+    fn references_are_typed_record_links() {
+        let statements = Book::define_table();
+        assert!(statements.contains(&"DEFINE FIELD OVERWRITE author ON TABLE books TYPE record<authors>;".to_string()));
+        assert!(statements
+            .contains(&"DEFINE FIELD OVERWRITE editors ON TABLE books TYPE array<record<authors>>;".to_string()));
+        assert_eq!(Book::reference_paths(), ["author", "editors"]);
+    }
+
+    #[test]
+    fn keys_must_point_at_the_target_table() {
+        let ok: Reference<Author> = serde_json::from_str(r#""authors:ada""#).unwrap();
+        assert_eq!(ok, Reference::new("ada"));
+        let bare: Reference<Author> = serde_json::from_str(r#""ada""#).unwrap();
+        assert_eq!(bare, Reference::new("ada"));
+        let err = serde_json::from_str::<Reference<Author>>(r#""books:ada""#).unwrap_err();
+        assert!(err.to_string().contains("expected a record of `authors`"), "{}", err);
+    }
+
+    #[test]
+    fn malformed_links_are_rejected() {
+        let read = |json: &str| serde_json::from_str::<Reference<Author>>(json).unwrap_err().to_string();
+        for empty in [r#""authors:""#, r#"":ada""#, r#""""#] {
+            assert!(read(empty).starts_with("invalid value: string"), "{empty}: {}", read(empty));
+        }
+
+        let link: Reference<Author> = serde_json::from_str(r#"{ "tb": "authors", "id": { "String": "ada" } }"#).unwrap();
+        assert_eq!(link, Reference::new("ada"));
+        assert!(read(r#"{ "tb": "authors" }"#).starts_with("missing field `id`"));
+        assert!(read(r#"{ "tb": "authors", "key": "ada" }"#).starts_with("unknown field `key`, expected `tb` or `id`"));
+        let extra = read(r#"{ "tb": "authors", "id": { "String": "ada" }, "name": "Ada" }"#);
+        assert!(extra.starts_with("unknown field `name`"), "{extra}");
+    }
+
+    #[tokio::test]
+    async fn fetch_resolves_links() {
+        let db = db().await;
+        db.query("CREATE authors:ada SET name = 'Ada'; CREATE authors:grace SET name = 'Grace';")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let book = Book {
+            id: None,
+            title: "Notes".to_string(),
+            author: Reference::new("ada"),
+            editors: vec![Reference::new("grace")],
+        };
+        let created: Option<Book> = db.create(("books", "notes")).content(book).await.unwrap();
+        let created = created.unwrap();
+        assert_eq!(created.author.id().to_string(), "authors:ada");
+        assert!(created.author.get().is_none(), "plain reads only return the link");
+
+        let fetched: Book = fetch(&db, "notes").await.unwrap().unwrap();
+        assert_eq!(fetched.author.get().unwrap().name, "Ada");
+        assert_eq!(fetched.editors[0].get().unwrap().name, "Grace");
+        assert_eq!(fetch_all::<Book>(&db).await.unwrap().len(), 1);
+
+        // Saving a fetched document stores links again, not copies.
+        let saved: Option<Book> = db.update(("books", "notes")).content(fetched).await.unwrap();
+        assert!(saved.unwrap().author.get().is_none());
+
+        let mut author = created.author;
+        assert_eq!(author.load(&db).await.unwrap().unwrap().name, "Ada");
+    }
+
+    #[tokio::test]
+    async fn links_to_other_tables_are_rejected() {
+        let db = db().await;
+        let bad = db
+            .query("CREATE books SET title = 'x', author = books:nope, editors = []")
+            .await
+            .unwrap()
+            .check();
+        assert!(bad.is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use surrealdb::sql::{Id, Thing};
+
+use super::{
+    assign_block_ids, block_id, insert_at, new_block_id, CounterRepository, DocumentRepository, PostRepository,
+    Record, RepositoryError, RepositoryResult,
+};
+use crate::schema::{Block, Counter, Post, PostBlock};
+
+/// One table, keyed by record key. A `BTreeMap` so `list` comes back ordered
+/// by id, the same as a SurrealDB table scan.
+struct Table<T> {
+    rows: RwLock<BTreeMap<String, T>>,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Self { rows: RwLock::new(BTreeMap::new()) }
+    }
+}
+
+impl<T: Record> Table<T> {
+    fn list(&self) -> Vec<T> {
+        self.rows.read().unwrap().values().cloned().collect()
+    }
+
+    fn get(&self, id: &str) -> Option<T> {
+        self.rows.read().unwrap().get(id).cloned()
+    }
+
+    fn create(&self, mut record: T) -> T {
+        let key = Id::rand().to_raw();
+        record.set_id(Thing::from((T::TABLE, key.as_str())));
+        self.rows.write().unwrap().insert(key, record.clone());
+        record
+    }
+
+    fn update(&self, id: &str, mut record: T) -> Option<T> {
+        let mut rows = self.rows.write().unwrap();
+        let slot = rows.get_mut(id)?;
+        record.set_id(Thing::from((T::TABLE, id)));
+        *slot = record.clone();
+        Some(record)
+    }
+
+    fn delete(&self, id: &str) -> Option<T> {
+        self.rows.write().unwrap().remove(id)
+    }
+
+    /// Changes one row in place; `None` if there is no such row.
+    fn modify<R>(&self, id: &str, change: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.rows.write().unwrap().get_mut(id).map(change)
+    }
+}
+
+fn block_index(post: &Post, block_key: &str) -> RepositoryResult<usize> {
+    let id = block_id(block_key);
+    post.blocks.iter().position(|b| b.id.as_ref() == Some(&id)).ok_or(RepositoryError::NotFound("Block"))
+}
+
+#[derive(Default)]
+pub struct MemoryPostRepository {
+    table: Table<Post>,
+}
+
+impl MemoryPostRepository {
+    fn modify_post<R>(&self, id: &str, change: impl FnOnce(&mut Post) -> RepositoryResult<R>) -> RepositoryResult<R> {
+        self.table.modify(id, change).unwrap_or(Err(RepositoryError::NotFound("Post")))
+    }
+}
+
+#[async_trait]
+impl DocumentRepository<Post> for MemoryPostRepository {
+    async fn list(&self) -> RepositoryResult<Vec<Post>> {
+        Ok(self.table.list())
+    }
+
+    async fn get(&self, id: &str) -> RepositoryResult<Option<Post>> {
+        Ok(self.table.get(id))
+    }
+
+    async fn create(&self, mut post: Post) -> RepositoryResult<Post> {
+        assign_block_ids(&[], &mut post.blocks);
+        Ok(self.table.create(post))
+    }
+
+    async fn update(&self, id: &str, mut post: Post) -> RepositoryResult<Option<Post>> {
+        let Some(existing) = self.table.get(id) else {
+            return Ok(None);
+        };
+        let existing: Vec<_> = existing.blocks.into_iter().filter_map(|b| b.id).collect();
+        assign_block_ids(&existing, &mut post.blocks);
+        Ok(self.table.update(id, post))
+    }
+
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<Post>> {
+        Ok(self.table.delete(id))
+    }
+}
+
+#[async_trait]
+impl PostRepository for MemoryPostRepository {
+    async fn create_block(&self, post_id: &str, block: Block, position: Option<usize>) -> RepositoryResult<PostBlock> {
+        self.modify_post(post_id, |post| {
+            let block = PostBlock { id: Some(new_block_id()), block };
+            insert_at(&mut post.blocks, position, block.clone());
+            Ok(block)
+        })
+    }
+
+    async fn update_block(&self, post_id: &str, block_id: &str, block: Block) -> RepositoryResult<PostBlock> {
+        self.modify_post(post_id, |post| {
+            let index = block_index(post, block_id)?;
+            post.blocks[index].block = block;
+            Ok(post.blocks[index].clone())
+        })
+    }
+
+    async fn delete_block(&self, post_id: &str, block_id: &str) -> RepositoryResult<PostBlock> {
+        self.modify_post(post_id, |post| {
+            let index = block_index(post, block_id)?;
+            Ok(post.blocks.remove(index))
+        })
+    }
+
+    async fn move_block(&self, post_id: &str, block_id: &str, position: usize) -> RepositoryResult<Post> {
+        self.modify_post(post_id, |post| {
+            let index = block_index(post, block_id)?;
+            let block = post.blocks.remove(index);
+            insert_at(&mut post.blocks, Some(position), block);
+            Ok(post.clone())
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryCounterRepository {
+    table: Table<Counter>,
+}
+
+#[async_trait]
+impl CounterRepository for MemoryCounterRepository {
+    async fn list(&self) -> RepositoryResult<Vec<Counter>> {
+        Ok(self.table.list())
+    }
+
+    async fn get(&self, id: &str) -> RepositoryResult<Option<Counter>> {
+        Ok(self.table.get(id))
+    }
+
+    async fn create(&self, counter: Counter) -> RepositoryResult<Counter> {
+        Ok(self.table.create(counter))
+    }
+
+    async fn update(&self, id: &str, counter: Counter) -> RepositoryResult<Option<Counter>> {
+        Ok(self.table.update(id, counter))
+    }
+
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<Counter>> {
+        Ok(self.table.delete(id))
+    }
+}
+
+/// Any [`Record`] type, one table of them.
+pub struct MemoryDocumentRepository<T> {
+    table: Table<T>,
+}
+
+impl<T> Default for MemoryDocumentRepository<T> {
+    fn default() -> Self {
+        Self { table: Table::default() }
+    }
+}
+
+#[async_trait]
+impl<T: Record> DocumentRepository<T> for MemoryDocumentRepository<T> {
+    async fn list(&self) -> RepositoryResult<Vec<T>> {
+        Ok(self.table.list())
+    }
+
+    async fn get(&self, id: &str) -> RepositoryResult<Option<T>> {
+        Ok(self.table.get(id))
+    }
+
+    async fn create(&self, document: T) -> RepositoryResult<T> {
+        Ok(self.table.create(document))
+    }
+
+    async fn update(&self, id: &str, document: T) -> RepositoryResult<Option<T>> {
+        Ok(self.table.update(id, document))
+    }
+
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<T>> {
+        Ok(self.table.delete(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(title: &str) -> Post {
+        Post { id: None, title: title.into(), blocks: vec![] }
+    }
+
+    #[tokio::test]
+    async fn post_crud_round_trip() {
+        let repo = MemoryPostRepository::default();
+
+        let created = repo.create(post("Hello")).await.unwrap();
+        let id = created.id.clone().expect("create assigns an id");
+        assert_eq!(id.tb, Post::TABLE);
+        let key = id.id.to_raw();
+
+        let fetched = repo.get(&key).await.unwrap().expect("post exists");
+        assert_eq!(fetched.title, "Hello");
+
+        let updated = repo.update(&key, post("Renamed")).await.unwrap().unwrap();
+        assert_eq!(updated.title, "Renamed");
+        assert_eq!(updated.id, Some(id));
+        assert_eq!(repo.list().await.unwrap().len(), 1);
+
+        assert!(repo.delete(&key).await.unwrap().is_some());
+        assert!(repo.get(&key).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn update_of_missing_post_is_none() {
+        let repo = MemoryPostRepository::default();
+        assert!(repo.update("missing", post("x")).await.unwrap().is_none());
+        assert!(repo.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn blocks_keep_their_ids_and_order() {
+        let repo = MemoryPostRepository::default();
+        let mut draft = post("Hello");
+        draft.blocks = crate::schema::default_page_schema().into_iter().map(PostBlock::from).collect();
+        let created = repo.create(draft).await.unwrap();
+        let key = created.id.unwrap().id.to_raw();
+        let footer = created.blocks[1].id.clone().unwrap().id.to_raw();
+
+        let moved = repo.move_block(&key, &footer, 0).await.unwrap();
+        assert!(matches!(moved.blocks[0].block, Block::Footer(_)));
+        assert!(repo.delete_block(&key, &footer).await.is_ok());
+        let block = moved.blocks[0].block.clone();
+        assert!(matches!(repo.update_block(&key, &footer, block).await, Err(RepositoryError::NotFound("Block"))));
+        assert!(matches!(repo.move_block("missing", &footer, 0).await, Err(RepositoryError::NotFound("Post"))));
+        assert_eq!(repo.get(&key).await.unwrap().unwrap().blocks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn counters_are_stored_in_their_own_table() {
+        let repo = MemoryCounterRepository::default();
+        let counter = repo.create(Counter { id: None, count: 3 }).await.unwrap();
+        assert_eq!(counter.id.unwrap().tb, Counter::TABLE);
+        assert_eq!(repo.list().await.unwrap()[0].count, 3);
+    }
+}
@@ -0,0 +1,137 @@
+//! Storage for documents, behind traits so handlers never touch table names or
+//! query builders directly. [`surreal`] talks to the configured SurrealDB
+//! engine; [`memory`] keeps everything in a map for tests.
+
+pub mod memory;
+pub mod surreal;
+
+use std::fmt;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use surrealdb::sql::{Id, Thing};
+
+use crate::schema::{Block, Counter, Post, PostBlock};
+
+pub use memory::{MemoryCounterRepository, MemoryDocumentRepository, MemoryPostRepository};
+pub use surreal::{SurrealCounterRepository, SurrealDocumentRepository, SurrealPostRepository};
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    Database(Box<surrealdb::Error>),
+    /// The store accepted the write but did not hand the record back.
+    MissingRecord(&'static str),
+    /// What a block write names does not exist: "Post" or "Block".
+    NotFound(&'static str),
+}
+
+pub type RepositoryResult<T> = Result<T, RepositoryError>;
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::Database(e) => write!(f, "{}", e),
+            RepositoryError::MissingRecord(table) => {
+                write!(f, "write to `{}` returned no record", table)
+            }
+            RepositoryError::NotFound(what) => write!(f, "{} not found", what),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+impl From<surrealdb::Error> for RepositoryError {
+    fn from(e: surrealdb::Error) -> Self {
+        RepositoryError::Database(Box::new(e))
+    }
+}
+
+/// A record that carries its own SurrealDB id.
+pub trait Record: Serialize + DeserializeOwned + Clone + Send + Sync + 'static {
+    const TABLE: &'static str;
+    fn id(&self) -> Option<&Thing>;
+    fn set_id(&mut self, id: Thing);
+}
+
+impl Record for Post {
+    const TABLE: &'static str = Post::TABLE;
+    fn id(&self) -> Option<&Thing> {
+        self.id.as_ref()
+    }
+    fn set_id(&mut self, id: Thing) {
+        self.id = Some(id);
+    }
+}
+
+impl Record for Counter {
+    const TABLE: &'static str = Counter::TABLE;
+    fn id(&self) -> Option<&Thing> {
+        self.id.as_ref()
+    }
+    fn set_id(&mut self, id: Thing) {
+        self.id = Some(id);
+    }
+}
+
+/// The documents of one table. `id` is always the bare record key
+/// (`abc123`), never `posts:abc123`.
+#[async_trait]
+pub trait DocumentRepository<T>: Send + Sync {
+    async fn list(&self) -> RepositoryResult<Vec<T>>;
+    async fn get(&self, id: &str) -> RepositoryResult<Option<T>>;
+    async fn create(&self, document: T) -> RepositoryResult<T>;
+    /// Replaces the stored document; `None` if none has that id.
+    async fn update(&self, id: &str, document: T) -> RepositoryResult<Option<T>>;
+    /// Returns the deleted document; `None` if none has that id.
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<T>>;
+}
+
+/// Posts are documents whose blocks can also be edited one at a time.
+/// Each fails with [`RepositoryError::NotFound`] naming what is missing:
+/// the post, or the block in it.
+#[async_trait]
+pub trait PostRepository: DocumentRepository<Post> {
+    /// Adds a block at `position` (the end if `None` or past it).
+    async fn create_block(&self, post_id: &str, block: Block, position: Option<usize>) -> RepositoryResult<PostBlock>;
+    /// Replaces one block.
+    async fn update_block(&self, post_id: &str, block_id: &str, block: Block) -> RepositoryResult<PostBlock>;
+    /// Returns the deleted block.
+    async fn delete_block(&self, post_id: &str, block_id: &str) -> RepositoryResult<PostBlock>;
+    /// Moves one block to `position` and returns the reordered post.
+    async fn move_block(&self, post_id: &str, block_id: &str, position: usize) -> RepositoryResult<Post>;
+}
+
+fn block_id(key: &str) -> Thing {
+    Thing::from((PostBlock::TABLE, key))
+}
+
+fn new_block_id() -> Thing {
+    block_id(&Id::rand().to_raw())
+}
+
+/// Keeps the ids of blocks the post already has and gives every other block
+/// (new, or claiming another post's block) a fresh one.
+fn assign_block_ids(existing: &[Thing], blocks: &mut [PostBlock]) {
+    for block in blocks {
+        if !block.id.as_ref().is_some_and(|id| existing.contains(id)) {
+            block.id = Some(new_block_id());
+        }
+    }
+}
+
+/// Inserts at `position`, clamped to the end.
+fn insert_at<T>(items: &mut Vec<T>, position: Option<usize>, item: T) {
+    let at = position.map_or(items.len(), |p| p.min(items.len()));
+    items.insert(at, item);
+}
+
+#[async_trait]
+pub trait CounterRepository: Send + Sync {
+    async fn list(&self) -> RepositoryResult<Vec<Counter>>;
+    async fn get(&self, id: &str) -> RepositoryResult<Option<Counter>>;
+    async fn create(&self, counter: Counter) -> RepositoryResult<Counter>;
+    async fn update(&self, id: &str, counter: Counter) -> RepositoryResult<Option<Counter>>;
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<Counter>>;
+}
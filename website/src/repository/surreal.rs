@@ -0,0 +1,297 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use surrealdb::sql::{Id, Thing};
+
+use super::{
+    assign_block_ids, insert_at, new_block_id, CounterRepository, DocumentRepository, PostRepository, Record,
+    RepositoryError, RepositoryResult,
+};
+use crate::db::Db;
+use crate::reference::{fetch, fetch_all};
+use crate::telemetry::db_call;
+use crate::schema::{Block, Counter, Post, PostBlock, PostRecord};
+
+/// Posts live in `posts`, their blocks in `blocks`; every read resolves the
+/// links with `FETCH`, and every write that touches both runs in one
+/// transaction. Every write also stamps the post's `updated_at`, so live
+/// queries on `posts` hear about block edits too.
+pub struct SurrealPostRepository {
+    db: Arc<Db>,
+}
+
+impl SurrealPostRepository {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { db }
+    }
+
+    /// The stored post, with its block links unresolved.
+    async fn record(&self, id: &str) -> RepositoryResult<Option<PostRecord>> {
+        Ok(db_call("select", Post::TABLE, async {
+            self.db.select((Post::TABLE, id)).await
+        })
+        .await?)
+    }
+
+    /// The ids of the post's blocks, in order.
+    async fn block_ids(&self, post_id: &str) -> RepositoryResult<Vec<Thing>> {
+        let record = self.record(post_id).await?.ok_or(RepositoryError::NotFound("Post"))?;
+        Ok(block_ids(&record))
+    }
+
+    /// Creates or updates every block, points the post at them in order and
+    /// deletes the blocks it no longer has. `existing` are the post's current
+    /// block ids.
+    async fn write(&self, op: &'static str, id: &str, existing: Vec<Thing>, mut post: Post) -> RepositoryResult<()> {
+        assign_block_ids(&existing, &mut post.blocks);
+        let ids: Vec<Thing> = post.blocks.iter().filter_map(|b| b.id.clone()).collect();
+        let removed: Vec<Thing> = existing.into_iter().filter(|id| !ids.contains(id)).collect();
+        let statement = if op == "create" { "CREATE" } else { "UPDATE" };
+        let sql = format!(
+            "BEGIN TRANSACTION;
+             FOR $block IN $blocks {{ UPSERT $block.id CONTENT $block; }};
+             {} $post CONTENT {{ title: $title, blocks: $ids, updated_at: time::now() }};
+             DELETE $removed;
+             COMMIT TRANSACTION;",
+            statement
+        );
+        db_call(op, Post::TABLE, async {
+            self.db
+                .query(sql)
+                .bind(("post", Thing::from((Post::TABLE, id))))
+                .bind(("title", post.title))
+                .bind(("blocks", post.blocks))
+                .bind(("ids", ids))
+                .bind(("removed", removed))
+                .await?
+                .check()
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Points the post at `ids`, in that order.
+    async fn set_block_order(&self, post_id: &str, ids: Vec<Thing>) -> RepositoryResult<()> {
+        db_call("update", Post::TABLE, async {
+            self.db
+                .query("UPDATE $post SET blocks = $ids, updated_at = time::now()")
+                .bind(("post", Thing::from((Post::TABLE, post_id))))
+                .bind(("ids", ids))
+                .await?
+                .check()
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+fn block_ids(record: &PostRecord) -> Vec<Thing> {
+    record.blocks.iter().map(|b| b.id().clone()).collect()
+}
+
+#[async_trait]
+impl DocumentRepository<Post> for SurrealPostRepository {
+    async fn list(&self) -> RepositoryResult<Vec<Post>> {
+        let records = fetch_all::<PostRecord>(&self.db).await?;
+        Ok(records.into_iter().map(Post::from).collect())
+    }
+
+    async fn get(&self, id: &str) -> RepositoryResult<Option<Post>> {
+        Ok(fetch::<PostRecord>(&self.db, id).await?.map(Post::from))
+    }
+
+    async fn create(&self, post: Post) -> RepositoryResult<Post> {
+        let id = Id::rand().to_raw();
+        self.write("create", &id, vec![], post).await?;
+        self.get(&id).await?.ok_or(RepositoryError::MissingRecord(Post::TABLE))
+    }
+
+    async fn update(&self, id: &str, post: Post) -> RepositoryResult<Option<Post>> {
+        let Some(record) = self.record(id).await? else {
+            return Ok(None);
+        };
+        self.write("update", id, block_ids(&record), post).await?;
+        self.get(id).await
+    }
+
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<Post>> {
+        let Some(post) = self.get(id).await? else {
+            return Ok(None);
+        };
+        let blocks: Vec<Thing> = post.blocks.iter().filter_map(|b| b.id.clone()).collect();
+        db_call("delete", Post::TABLE, async {
+            self.db
+                .query("BEGIN TRANSACTION; DELETE $blocks; DELETE $post; COMMIT TRANSACTION;")
+                .bind(("blocks", blocks))
+                .bind(("post", Thing::from((Post::TABLE, id))))
+                .await?
+                .check()
+        })
+        .await?;
+        Ok(Some(post))
+    }
+}
+
+#[async_trait]
+impl PostRepository for SurrealPostRepository {
+    async fn create_block(&self, post_id: &str, block: Block, position: Option<usize>) -> RepositoryResult<PostBlock> {
+        let id = new_block_id();
+        let mut ids = self.block_ids(post_id).await?;
+        insert_at(&mut ids, position, id.clone());
+        db_call("create", PostBlock::TABLE, async {
+            self.db
+                .query("BEGIN TRANSACTION; CREATE $block CONTENT $content; UPDATE $post SET blocks = $ids, updated_at = time::now(); COMMIT TRANSACTION;")
+                .bind(("block", id.clone()))
+                .bind(("content", block.clone()))
+                .bind(("post", Thing::from((Post::TABLE, post_id))))
+                .bind(("ids", ids))
+                .await?
+                .check()
+        })
+        .await?;
+        Ok(PostBlock { id: Some(id), block })
+    }
+
+    async fn update_block(&self, post_id: &str, block_id: &str, block: Block) -> RepositoryResult<PostBlock> {
+        let id = super::block_id(block_id);
+        let ids = self.block_ids(post_id).await?;
+        if !ids.contains(&id) {
+            return Err(RepositoryError::NotFound("Block"));
+        }
+        let updated: Option<PostBlock> = db_call("update", PostBlock::TABLE, async {
+            self.db
+                .query("BEGIN TRANSACTION; UPDATE ONLY $block CONTENT $content; UPDATE $post SET blocks = $ids, updated_at = time::now(); COMMIT TRANSACTION;")
+                .bind(("block", id))
+                .bind(("content", block))
+                .bind(("post", Thing::from((Post::TABLE, post_id))))
+                .bind(("ids", ids))
+                .await?
+                .take(0)
+        })
+        .await?;
+        updated.ok_or(RepositoryError::MissingRecord(PostBlock::TABLE))
+    }
+
+    async fn delete_block(&self, post_id: &str, block_id: &str) -> RepositoryResult<PostBlock> {
+        let id = super::block_id(block_id);
+        let mut ids = self.block_ids(post_id).await?;
+        let index = ids.iter().position(|b| *b == id).ok_or(RepositoryError::NotFound("Block"))?;
+        ids.remove(index);
+        let deleted: Option<PostBlock> = db_call("delete", PostBlock::TABLE, async {
+            self.db
+                .query("BEGIN TRANSACTION; DELETE ONLY $block RETURN BEFORE; UPDATE $post SET blocks = $ids, updated_at = time::now(); COMMIT TRANSACTION;")
+                .bind(("block", id))
+                .bind(("post", Thing::from((Post::TABLE, post_id))))
+                .bind(("ids", ids))
+                .await?
+                .take(0)
+        })
+        .await?;
+        deleted.ok_or(RepositoryError::NotFound("Block"))
+    }
+
+    async fn move_block(&self, post_id: &str, block_id: &str, position: usize) -> RepositoryResult<Post> {
+        let id = super::block_id(block_id);
+        let mut ids = self.block_ids(post_id).await?;
+        let index = ids.iter().position(|b| *b == id).ok_or(RepositoryError::NotFound("Block"))?;
+        let id = ids.remove(index);
+        insert_at(&mut ids, Some(position), id);
+        self.set_block_order(post_id, ids).await?;
+        self.get(post_id).await?.ok_or(RepositoryError::NotFound("Post"))
+    }
+}
+
+pub struct SurrealCounterRepository {
+    db: Arc<Db>,
+}
+
+impl SurrealCounterRepository {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CounterRepository for SurrealCounterRepository {
+    async fn list(&self) -> RepositoryResult<Vec<Counter>> {
+        Ok(db_call("select", Counter::TABLE, async {
+            self.db.select(Counter::TABLE).await
+        })
+        .await?)
+    }
+
+    async fn get(&self, id: &str) -> RepositoryResult<Option<Counter>> {
+        Ok(db_call("select", Counter::TABLE, async {
+            self.db.select((Counter::TABLE, id)).await
+        })
+        .await?)
+    }
+
+    async fn create(&self, counter: Counter) -> RepositoryResult<Counter> {
+        let created: Option<Counter> = db_call("create", Counter::TABLE, async {
+            self.db.create(Counter::TABLE).content(counter).await
+        })
+        .await?;
+        created.ok_or(RepositoryError::MissingRecord(Counter::TABLE))
+    }
+
+    async fn update(&self, id: &str, counter: Counter) -> RepositoryResult<Option<Counter>> {
+        Ok(db_call("update", Counter::TABLE, async {
+            self.db.update((Counter::TABLE, id)).content(counter).await
+        })
+        .await?)
+    }
+
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<Counter>> {
+        Ok(db_call("delete", Counter::TABLE, async {
+            self.db.delete((Counter::TABLE, id)).await
+        })
+        .await?)
+    }
+}
+
+/// A table of `T` as they are: no links to resolve, nothing to split out.
+pub struct SurrealDocumentRepository<T> {
+    db: Arc<Db>,
+    record: PhantomData<fn() -> T>,
+}
+
+impl<T> SurrealDocumentRepository<T> {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { db, record: PhantomData }
+    }
+}
+
+#[async_trait]
+impl<T: Record> DocumentRepository<T> for SurrealDocumentRepository<T> {
+    async fn list(&self) -> RepositoryResult<Vec<T>> {
+        Ok(db_call("select", T::TABLE, async { self.db.select(T::TABLE).await }).await?)
+    }
+
+    async fn get(&self, id: &str) -> RepositoryResult<Option<T>> {
+        Ok(db_call("select", T::TABLE, async { self.db.select((T::TABLE, id)).await }).await?)
+    }
+
+    async fn create(&self, mut document: T) -> RepositoryResult<T> {
+        document.set_id(Thing::from((T::TABLE, Id::rand().to_raw().as_str())));
+        let created: Option<T> = db_call("create", T::TABLE, async {
+            self.db.create(T::TABLE).content(document).await
+        })
+        .await?;
+        created.ok_or(RepositoryError::MissingRecord(T::TABLE))
+    }
+
+    async fn update(&self, id: &str, mut document: T) -> RepositoryResult<Option<T>> {
+        // The body may name another record; the path wins.
+        document.set_id(Thing::from((T::TABLE, id)));
+        Ok(db_call("update", T::TABLE, async {
+            self.db.update((T::TABLE, id)).content(document).await
+        })
+        .await?)
+    }
+
+    async fn delete(&self, id: &str) -> RepositoryResult<Option<T>> {
+        Ok(db_call("delete", T::TABLE, async { self.db.delete((T::TABLE, id)).await }).await?)
+    }
+}
@@ -0,0 +1,346 @@
+//! Rich text stored as a Portable-Text-style AST rather than HTML: a list of
+//! text blocks (paragraphs, headings, quotes, list items), each holding
+//! spans of plain text with marks. Rendering is the only place HTML is
+//! produced, so every string is escaped and only safe link targets survive.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tera::Value;
+
+use crate::fields::Fields;
+use crate::surql::Schema;
+use crate::validation::Validate;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct RichText {
+    #[field(label = "Rich text", hint = "The text as JSON", form_type = Json)]
+    pub body: Vec<TextBlock>,
+}
+
+/// One paragraph, heading, quote or list item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Schema)]
+pub struct TextBlock {
+    pub style: TextStyle,
+    /// Set for list items; consecutive items form one list.
+    #[serde(default)]
+    pub list_item: Option<ListItem>,
+    pub children: Vec<Span>,
+    /// Annotations that spans refer to by `key` in their `marks`.
+    #[serde(default)]
+    pub mark_defs: Vec<Link>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Schema)]
+pub enum TextStyle {
+    Normal,
+    H1,
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+    Blockquote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Schema)]
+pub struct ListItem {
+    pub kind: ListKind,
+    /// Nesting depth, starting at 1.
+    pub level: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Schema)]
+pub enum ListKind {
+    Bullet,
+    Number,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Schema)]
+pub struct Span {
+    pub text: String,
+    /// Decorators (`strong`, `em`, `code`, `underline`, `strike`) or the
+    /// `key` of one of the block's `mark_defs`. Unknown marks are ignored.
+    #[serde(default)]
+    pub marks: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Schema)]
+pub struct Link {
+    pub key: String,
+    pub href: String,
+}
+
+impl TextStyle {
+    fn tag(self) -> &'static str {
+        match self {
+            TextStyle::Normal => "p",
+            TextStyle::H1 => "h1",
+            TextStyle::H2 => "h2",
+            TextStyle::H3 => "h3",
+            TextStyle::H4 => "h4",
+            TextStyle::H5 => "h5",
+            TextStyle::H6 => "h6",
+            TextStyle::Blockquote => "blockquote",
+        }
+    }
+
+    /// 1 to 6 for headings, `None` for paragraphs and quotes.
+    pub fn heading_level(self) -> Option<u8> {
+        match self {
+            TextStyle::H1 => Some(1),
+            TextStyle::H2 => Some(2),
+            TextStyle::H3 => Some(3),
+            TextStyle::H4 => Some(4),
+            TextStyle::H5 => Some(5),
+            TextStyle::H6 => Some(6),
+            TextStyle::Normal | TextStyle::Blockquote => None,
+        }
+    }
+}
+
+impl ListKind {
+    fn tag(self) -> &'static str {
+        match self {
+            ListKind::Bullet => "ul",
+            ListKind::Number => "ol",
+        }
+    }
+}
+
+impl RichText {
+    /// The HTML for the whole text. Text and attributes are escaped, and a
+    /// link whose target is not `http(s)`, `mailto`, `tel` or relative is
+    /// rendered as plain text.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        // Lists currently open, outermost first; each has an open `<li>`.
+        let mut lists: Vec<ListKind> = Vec::new();
+
+        for block in &self.body {
+            let Some(item) = block.list_item else {
+                close_lists(&mut html, &mut lists, 0);
+                let tag = block.style.tag();
+                let _ = write!(html, "<{}>", tag);
+                write_spans(&mut html, block);
+                let _ = write!(html, "</{}>", tag);
+                continue;
+            };
+
+            // Never open more than one level at a time.
+            let level = usize::from(item.level.max(1)).min(lists.len() + 1);
+            close_lists(&mut html, &mut lists, level);
+            if lists.len() == level && lists.last() != Some(&item.kind) {
+                close_lists(&mut html, &mut lists, level - 1);
+            }
+            if lists.len() == level {
+                html.push_str("</li>");
+            } else {
+                let _ = write!(html, "<{}>", item.kind.tag());
+                lists.push(item.kind);
+            }
+            html.push_str("<li>");
+            write_spans(&mut html, block);
+        }
+        close_lists(&mut html, &mut lists, 0);
+        html
+    }
+
+    /// The text without any markup, e.g. for excerpts and search.
+    pub fn plain_text(&self) -> String {
+        self.body
+            .iter()
+            .map(|block| block.children.iter().map(|s| s.text.as_str()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Closes open lists until only `depth` remain.
+fn close_lists(html: &mut String, lists: &mut Vec<ListKind>, depth: usize) {
+    while lists.len() > depth {
+        let kind = lists.pop().expect("non-empty");
+        let _ = write!(html, "</li></{}>", kind.tag());
+    }
+}
+
+fn write_spans(html: &mut String, block: &TextBlock) {
+    let links: HashMap<&str, &str> = block.mark_defs.iter().map(|l| (l.key.as_str(), l.href.as_str())).collect();
+    for span in &block.children {
+        let mut closing = Vec::new();
+        for mark in &span.marks {
+            let tag = match mark.as_str() {
+                "strong" => "strong",
+                "em" => "em",
+                "code" => "code",
+                "underline" => "u",
+                "strike" => "s",
+                key => match links.get(key) {
+                    Some(href) if is_safe_href(href) => {
+                        let _ = write!(html, "<a href=\"{}\" rel=\"noopener noreferrer\">", escape(href));
+                        closing.push("a");
+                        continue;
+                    }
+                    _ => continue,
+                },
+            };
+            let _ = write!(html, "<{}>", tag);
+            closing.push(tag);
+        }
+        html.push_str(&escape(&span.text).replace('\n', "<br>"));
+        for tag in closing.iter().rev() {
+            let _ = write!(html, "</{}>", tag);
+        }
+    }
+}
+
+fn is_safe_href(href: &str) -> bool {
+    let href = href.trim();
+    // A scheme is whatever precedes the first `:`, if that comes before any
+    // `/`, `?` or `#`; anything else is a relative reference.
+    match href.find([':', '/', '?', '#']) {
+        Some(at) if href[at..].starts_with(':') => {
+            let scheme = href[..at].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto" | "tel")
+        }
+        _ => true,
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `{{ rich_text(value=block.RichText) }}`: renders a [`RichText`] value.
+/// Marked safe, since the output is already escaped.
+pub struct RichTextFunction;
+
+impl tera::Function for RichTextFunction {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let value = args
+            .get("value")
+            .ok_or_else(|| tera::Error::msg("rich_text() needs a `value` argument"))?;
+        let text: RichText = serde_json::from_value(value.clone())
+            .map_err(|e| tera::Error::msg(format!("rich_text(): not a rich text value: {}", e)))?;
+        Ok(Value::String(text.to_html()))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// `{{ block.CallToAction.href | safe_href }}`: the link unchanged if
+/// [`RichText::to_html`] would allow it, `#` otherwise. The result is still
+/// escaped like any other value.
+pub struct SafeHrefFilter;
+
+impl tera::Filter for SafeHrefFilter {
+    fn filter(&self, value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+        let href = value.as_str().ok_or_else(|| tera::Error::msg("safe_href expects a string"))?;
+        Ok(Value::String(if is_safe_href(href) { href.trim().to_string() } else { "#".to_string() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::{Context, Tera};
+
+    fn block(style: TextStyle, list_item: Option<ListItem>, text: &str) -> TextBlock {
+        TextBlock {
+            style,
+            list_item,
+            children: vec![Span { text: text.into(), marks: vec![] }],
+            mark_defs: vec![],
+        }
+    }
+
+    fn item(kind: ListKind, level: u8) -> Option<ListItem> {
+        Some(ListItem { kind, level })
+    }
+
+    #[test]
+    fn text_is_escaped_and_marks_nest() {
+        let text = RichText {
+            body: vec![TextBlock {
+                style: TextStyle::Normal,
+                list_item: None,
+                children: vec![
+                    Span { text: "<script>alert(1)</script> & ".into(), marks: vec![] },
+                    Span { text: "docs".into(), marks: vec!["strong".into(), "l1".into()] },
+                    Span { text: "bad".into(), marks: vec!["l2".into(), "blink".into()] },
+                ],
+                mark_defs: vec![
+                    Link { key: "l1".into(), href: "https://example.com/?a=1&b=\"2\"".into() },
+                    Link { key: "l2".into(), href: " JavaScript:alert(1)".into() },
+                ],
+            }],
+        };
+        assert_eq!(
+            text.to_html(),
+            "<p>&lt;script&gt;alert(1)&lt;/script&gt; &amp; \
+             <strong><a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\" rel=\"noopener noreferrer\">docs</a></strong>\
+             bad</p>"
+        );
+    }
+
+    #[test]
+    fn list_items_group_and_nest() {
+        let text = RichText {
+            body: vec![
+                block(TextStyle::H2, None, "Steps"),
+                block(TextStyle::Normal, item(ListKind::Number, 1), "one"),
+                block(TextStyle::Normal, item(ListKind::Bullet, 2), "detail"),
+                block(TextStyle::Normal, item(ListKind::Number, 1), "two"),
+                block(TextStyle::Blockquote, None, "done"),
+            ],
+        };
+        assert_eq!(
+            text.to_html(),
+            "<h2>Steps</h2><ol><li>one<ul><li>detail</li></ul></li><li>two</li></ol><blockquote>done</blockquote>"
+        );
+    }
+
+    #[test]
+    fn relative_and_mail_links_are_allowed() {
+        for href in ["/about", "#top", "?page=2", "mailto:hi@example.com", "a/b:c"] {
+            assert!(is_safe_href(href), "{}", href);
+        }
+        for href in ["javascript:x", "data:text/html,x", "vbscript:x"] {
+            assert!(!is_safe_href(href), "{}", href);
+        }
+    }
+
+    #[test]
+    fn tera_function_output_is_not_escaped_again() {
+        let mut tera = Tera::default();
+        tera.register_function("rich_text", RichTextFunction);
+        tera.add_raw_template("t.html", "{{ rich_text(value=text) }}").unwrap();
+        let mut context = Context::new();
+        context.insert("text", &RichText { body: vec![block(TextStyle::Normal, None, "a < b")] });
+        assert_eq!(tera.render("t.html", &context).unwrap(), "<p>a &lt; b</p>");
+    }
+
+    #[test]
+    fn json_schema_describes_the_ast() {
+        let schema = serde_json::to_value(schemars::schema_for!(RichText)).unwrap();
+        let definitions = &schema["definitions"];
+        assert_eq!(definitions["TextStyle"]["enum"][1], "H1");
+        assert_eq!(definitions["Span"]["properties"]["marks"]["type"], "array");
+        assert_eq!(definitions["TextBlock"]["required"], serde_json::json!(["children", "style"]));
+    }
+}
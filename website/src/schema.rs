@@ -1,59 +1,442 @@
+use chrono::{DateTime, SubsecRound, Utc};
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
 use surrealdb::sql::Thing;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum FormType {
-    InputArea,
-    InputText,
-    InputDate,
+use crate::fields::{self, Choice, Definitions, Fields, FormType};
+use crate::reference::Reference;
+use crate::rich_text::RichText;
+use crate::surql::{self, Schema, TableSchema};
+use crate::validation::{FieldError, Validate, ValidationErrors};
+
+/// The element a `Header` renders as: a heading level, or `p` for a line
+/// of text that stays out of the heading outline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Schema)]
+#[serde(rename_all = "lowercase")]
+pub enum HTMLElement {
+    H1,
+    #[default]
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+    P,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Field {
-    pub label: String,
-    pub hint: String,
-    pub form_type: FormType,
+impl HTMLElement {
+    /// The editor's choices, in order.
+    pub const OPTIONS: &'static [Choice] = &[
+        Choice { value: "h1", label: "h1" },
+        Choice { value: "h2", label: "h2" },
+        Choice { value: "h3", label: "h3" },
+        Choice { value: "h4", label: "h4" },
+        Choice { value: "h5", label: "h5" },
+        Choice { value: "h6", label: "h6" },
+        Choice { value: "p", label: "p" },
+    ];
+
+    /// 1 to 6 for headings, `None` for `p`.
+    pub fn level(self) -> Option<u8> {
+        match self {
+            HTMLElement::H1 => Some(1),
+            HTMLElement::H2 => Some(2),
+            HTMLElement::H3 => Some(3),
+            HTMLElement::H4 => Some(4),
+            HTMLElement::H5 => Some(5),
+            HTMLElement::H6 => Some(6),
+            HTMLElement::P => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
 pub struct Header {
-    pub content: Field,
+    #[field(label = "Header", form_type = InputArea)]
+    #[validate(required)]
+    pub content: String,
+    /// Headers saved before this existed render as `h2`, as they always did.
+    #[serde(default)]
+    #[field(hint = "h1 to h6, or p to keep it out of the outline", form_type = Select(HTMLElement::OPTIONS))]
+    pub element: HTMLElement,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
 pub struct Footer {
-    pub copyright: Field,
+    #[field(label = "Footer")]
+    pub copyright: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Image {
+    #[field(label = "Image URL", form_type = Url)]
+    #[validate(required)]
+    pub src: String,
+    #[field(label = "Alt text", hint = "Read out instead of the image; leave empty only if it is decorative")]
+    #[validate(length(max = 250))]
+    pub alt: String,
+    #[serde(default)]
+    pub caption: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Paragraph {
+    #[field(label = "Paragraph", form_type = InputArea)]
+    #[validate(required)]
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Quote {
+    #[field(form_type = InputArea)]
+    #[validate(required)]
+    pub text: String,
+    #[serde(default)]
+    pub attribution: Option<String>,
+    #[serde(default)]
+    #[field(label = "Source URL", form_type = Url)]
+    #[validate(url)]
+    pub cite: Option<String>,
+}
+
+/// Source code, highlighted when the page is rendered.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Code {
+    #[field(hint = "rust, js, python, sql, sh or json; anything else is not highlighted")]
+    pub language: String,
+    #[field(form_type = InputArea)]
+    #[validate(required)]
+    pub code: String,
+    #[serde(default)]
+    #[field(label = "File name")]
+    pub filename: Option<String>,
+}
+
+/// A self-hosted video. The fields are the ones Google reads from the
+/// `VideoObject` JSON-LD the block renders with it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Video {
+    #[validate(required, length(max = 100))]
+    pub name: String,
+    #[field(form_type = InputArea)]
+    #[validate(required)]
+    pub description: String,
+    #[field(label = "Thumbnail URL", form_type = Url)]
+    #[validate(required, url)]
+    pub thumbnail_url: String,
+    #[serde(serialize_with = "surql::datetime")]
+    #[field(label = "Uploaded (UTC)")]
+    #[validate(date_range(min = "2000-01-01T00:00:00Z"))]
+    pub upload_date: DateTime<Utc>,
+    #[serde(default)]
+    #[field(hint = "ISO 8601, e.g. PT4M12S")]
+    #[validate(regex(pattern = "^P(\\d+D)?(T(\\d+H)?(\\d+M)?(\\d+S)?)?$"))]
+    pub duration: Option<String>,
+    /// The file itself, which crawlers fetch to build previews.
+    #[field(label = "Video file URL", form_type = Url)]
+    #[validate(required, url)]
+    pub content_url: String,
+    #[serde(default)]
+    #[field(label = "Player page URL", hint = "A page that plays the video", form_type = Url)]
+    #[validate(url)]
+    pub embed_url: Option<String>,
+    #[serde(default)]
+    #[validate(nested)]
+    pub publisher: Option<Publisher>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Publisher {
+    #[field(label = "Publisher")]
+    pub name: String,
+    #[serde(default)]
+    #[field(label = "Publisher logo URL", form_type = Url)]
+    #[validate(url)]
+    pub logo: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Third-party content in a sandboxed iframe; only `https` URLs are shown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Embed {
+    #[field(label = "URL", hint = "https only", form_type = Url)]
+    #[validate(required, url)]
+    pub url: String,
+    #[field(hint = "Describes the embedded content to screen readers")]
+    #[validate(required)]
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct CallToAction {
+    #[validate(required)]
+    pub heading: String,
+    #[serde(default)]
+    #[field(form_type = InputArea)]
+    pub text: Option<String>,
+    #[field(label = "Button label")]
+    #[validate(required, length(max = 40))]
+    pub label: String,
+    #[field(label = "Button link")]
+    #[validate(required)]
+    pub href: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, Schema, Fields, Validate)]
+pub struct Gallery {
+    #[validate(length(min = 1), nested)]
+    pub images: Vec<Image>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Schema, Validate)]
 pub enum Block {
     Header(Header),
     Footer(Footer),
+    RichText(RichText),
+    Image(Image),
+    Paragraph(Paragraph),
+    Quote(Quote),
+    Code(Code),
+    Video(Video),
+    Embed(Embed),
+    CallToAction(CallToAction),
+    Gallery(Gallery),
+}
+
+impl Block {
+    /// The variant name, which is also the block's key when serialized.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Block::Header(_) => "Header",
+            Block::Footer(_) => "Footer",
+            Block::RichText(_) => "RichText",
+            Block::Image(_) => "Image",
+            Block::Paragraph(_) => "Paragraph",
+            Block::Quote(_) => "Quote",
+            Block::Code(_) => "Code",
+            Block::Video(_) => "Video",
+            Block::Embed(_) => "Embed",
+            Block::CallToAction(_) => "CallToAction",
+            Block::Gallery(_) => "Gallery",
+        }
+    }
+}
+
+/// One block of a post, stored as its own record in `blocks` so it can be
+/// edited, moved or deleted without rewriting the post. The block is
+/// flattened: a record looks like `{ id, Header: { ... } }`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PostBlock {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip)]
+    pub id: Option<Thing>,
+    #[serde(flatten)]
+    pub block: Block,
+}
+
+impl PostBlock {
+    pub const TABLE: &'static str = "blocks";
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl From<Block> for PostBlock {
+    fn from(block: Block) -> Self {
+        Self { id: None, block }
+    }
+}
+
+// Written by hand: the derive does not know `flatten`, and a flattened
+// block's fields are simply the `Block`'s own.
+impl Schema for PostBlock {
+    fn surql_type() -> String {
+        Block::surql_type()
+    }
+
+    fn define_fields(table: &str, path: &str, out: &mut Vec<String>) {
+        Block::define_fields(table, path, out);
+    }
+}
+
+impl Validate for PostBlock {
+    fn validate_into(&self, path: &str, errors: &mut Vec<FieldError>) {
+        self.block.validate_into(path, errors);
+    }
+}
+
+impl TableSchema for PostBlock {
+    const TABLE: &'static str = PostBlock::TABLE;
+
+    fn record_id(&self) -> Option<&Thing> {
+        self.id.as_ref()
+    }
+}
+
+/// A post as stored in `posts`: its blocks are links, in display order.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema)]
+#[schema(table = "posts")]
+pub struct PostRecord {
+    pub id: Option<Thing>,
+    pub title: String,
+    pub blocks: Vec<Reference<PostBlock>>,
+    /// When the post or one of its blocks last changed.
+    pub updated_at: Option<surrealdb::sql::Datetime>,
+}
+
+/// A post with its blocks resolved: what the repositories return and the
+/// handlers and templates work with.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Fields, Validate)]
 pub struct Post {
+    #[schemars(skip)]
+    pub id: Option<Thing>,
+    #[validate(required, length(max = 200))]
+    pub title: String,
+    /// Edited block by block, with each block type's own fields.
+    #[field(skip)]
+    #[validate(nested)]
+    #[serde(default)]
+    pub blocks: Vec<PostBlock>,
+}
+
+impl Post {
+    pub const TABLE: &'static str = PostRecord::TABLE;
+
+    /// A post from the editor's form input: the title as
+    /// [`Fields::from_form`] reads it, and `blocks` as a list of blocks
+    /// tagged with their type (see [`Definitions::parse_tagged`]). A block
+    /// the post already has also sends its key as `id`.
+    pub fn from_editor(definitions: &Definitions, form: &Value) -> Result<Self, ValidationErrors> {
+        match Self::read_editor(definitions, form) {
+            (post, errors) if errors.is_empty() => Ok(post),
+            (_, errors) => Err(ValidationErrors(errors)),
+        }
+    }
+
+    /// The post as entered in a form [`Post::from_editor`] rejected, to
+    /// edit again. A field that did not parse keeps the text entered when
+    /// it holds text and is blank otherwise; a block of no known type is
+    /// left out.
+    pub fn refill(definitions: &Definitions, form: &Value) -> Self {
+        Self::read_editor(definitions, form).0
+    }
+
+    fn read_editor(definitions: &Definitions, form: &Value) -> (Self, Vec<FieldError>) {
+        let (title, mut errors) = match Self::from_form(definitions, form) {
+            Ok(post) => (post.title, Vec::new()),
+            Err(errors) => (form.get("title").and_then(Value::as_str).unwrap_or_default().to_string(), errors.0),
+        };
+        let raw_blocks = fields::items(form.get("blocks").unwrap_or(&Value::Null)).unwrap_or_else(|| {
+            errors.push(FieldError { path: "blocks".into(), message: "must be a list".into() });
+            Vec::new()
+        });
+        let mut blocks = Vec::new();
+        for (i, (_, raw)) in raw_blocks.into_iter().enumerate() {
+            let path = format!("blocks[{}]", i);
+            let mut raw = raw.clone();
+            let key = raw.as_object_mut().and_then(|raw| raw.remove("id"));
+            let before = errors.len();
+            let parsed = definitions.parse_tagged(&raw, &path, &mut errors);
+            let block = match serde_json::from_value::<Block>(parsed.clone()) {
+                Ok(block) if errors.len() == before => Some(block),
+                Ok(_) => None,
+                Err(e) => {
+                    if errors.len() == before {
+                        errors.push(FieldError { path, message: e.to_string() });
+                    }
+                    None
+                }
+            };
+            if let Some(block) = block.or_else(|| as_entered(definitions, &raw, &parsed)) {
+                let id = key.as_ref().and_then(Value::as_str).map(|key| Thing::from((PostBlock::TABLE, key)));
+                blocks.push(PostBlock { id, block });
+            }
+        }
+        (Self { id: None, title, blocks }, errors)
+    }
+}
+
+/// A block that did not parse, from the blank block of its type: with each
+/// field that parsed, and the text entered in each that did not if the
+/// field is edited as text. The blank block itself if that still does not
+/// parse.
+fn as_entered(definitions: &Definitions, raw: &Value, parsed: &Value) -> Option<Block> {
+    let (name, entered) = raw.as_object()?.iter().next()?;
+    let blank = blank_blocks().into_iter().find(|block| block.type_name() == name)?;
+    let mut block = serde_json::to_value(&blank).ok()?;
+    for field in definitions.get(name)? {
+        let text = matches!(field.form_type, FormType::InputText | FormType::InputArea | FormType::Url);
+        let value = match (parsed.get(name).and_then(|parsed| parsed.get(field.name)), entered.get(field.name)) {
+            (Some(parsed), _) if !parsed.is_null() => parsed.clone(),
+            (_, Some(Value::String(entered))) if text => Value::String(entered.clone()),
+            _ => continue,
+        };
+        block[name][field.name] = value;
+    }
+    Some(serde_json::from_value(block).unwrap_or(blank))
+}
+
+impl From<PostRecord> for Post {
+    /// Links that did not resolve are dropped.
+    fn from(record: PostRecord) -> Self {
+        Self {
+            id: record.id,
+            title: record.title,
+            blocks: record.blocks.into_iter().filter_map(Reference::into_inner).collect(),
+        }
+    }
+}
+
+/// The field definitions of posts and of every block type, for the editor.
+pub fn definitions() -> Definitions {
+    Definitions::default()
+        .with::<Post>()
+        .with::<Header>()
+        .with::<Footer>()
+        .with::<RichText>()
+        .with::<Image>()
+        .with::<Paragraph>()
+        .with::<Quote>()
+        .with::<Code>()
+        .with::<Video>()
+        .with::<Publisher>()
+        .with::<Embed>()
+        .with::<CallToAction>()
+        .with::<Gallery>()
+}
+
+/// One empty block of every type, in the order the editor offers them.
+pub fn blank_blocks() -> Vec<Block> {
+    let mut blocks = default_page_schema();
+    for block in &mut blocks {
+        match block {
+            Block::Header(header) => header.content.clear(),
+            Block::Footer(footer) => footer.copyright.clear(),
+            _ => {}
+        }
+    }
+    blocks.extend([
+        Block::RichText(RichText::default()),
+        Block::Paragraph(Paragraph::default()),
+        Block::Image(Image::default()),
+        Block::Quote(Quote::default()),
+        Block::Code(Code::default()),
+        Block::Video(Video { upload_date: Utc::now().trunc_subsecs(0), ..Video::default() }),
+        Block::Embed(Embed::default()),
+        Block::CallToAction(CallToAction::default()),
+        Block::Gallery(Gallery { images: vec![Image::default()] }),
+    ]);
+    blocks
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Schema)]
+#[schema(table = "Counter")]
+pub struct Counter {
     pub id: Option<Thing>,
-    pub title: Field,
-    pub blocks: Vec<Block>,
+    pub count: i32,
 }
 
 pub fn default_page_schema() -> Vec<Block> {
     vec![
-        Block::Header(Header {
-            content: Field {
-                label: "Header".to_string(),
-                hint: "".to_string(),
-                form_type: FormType::InputArea,
-            },
-        }),
-        Block::Footer(Footer {
-            copyright: Field {
-                label: "Copyright".to_string(),
-                hint: "".to_string(),
-                form_type: FormType::InputText,
-            },
-        }),
+        Block::Header(Header { content: "Header".to_string(), element: HTMLElement::H2 }),
+        Block::Footer(Footer { copyright: "Copyright".to_string() }),
     ]
 }
 
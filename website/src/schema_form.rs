@@ -0,0 +1,976 @@
+//! HTML forms for types described by their `schemars` JSON Schema, such as
+//! `schema_v2::SeoMetadata`, rather than by `#[derive(Fields)]`.
+//!
+//! [`form`] walks the schema alongside the current value and returns a tree
+//! of [`FormField`]s; `forms::schema_field` in `macros/forms.html` renders
+//! it. Every input is named by its path, `open_graph.images[1].url`.
+//!
+//! - Objects (`$ref`s included) become fieldsets of their properties.
+//! - Arrays list an entry per item, plus a blank `template` item whose path
+//!   has `__index__` where the index goes.
+//! - Enums become selects; a `oneOf` of single-value variants uses each
+//!   variant's `title` as its label ("Sci-Fi"), and a variant with data
+//!   (`{ "Other": "..." }`) gets its own field after the select.
+//! - `Option`s (`null` in the type, or an `anyOf` with `null`) are not
+//!   required, nor is anything inside them.
+//! - A `widget` extension, such as `"textarea"`, picks the control, with
+//!   its `widgetOptions`; see [`crate::widgets`].
+//! - A `visibleIf` extension, `{ "field": "og_type", "equals": "Article" }`,
+//!   shows a property only while that sibling has that value; the field is
+//!   rendered `hidden` otherwise, and the editor toggles it.
+//! - Schemas the form cannot express, like `serde_json::Value`'s `true`,
+//!   are edited as JSON text.
+//!
+//! [`decode`] goes the other way: it reads the submitted `(name, text)`
+//! pairs back into the type, by the same schema. What it rejects, [`refill`]
+//! turns back into the form, as the author entered it, with each error on
+//! the field at its path. Neither keeps what a `visibleIf` hides, and
+//! [`clear_hidden`] drops it from values that did not come from a form.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use schemars::schema::RootSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::form_input;
+use crate::validation::{FieldError, Validate, ValidationErrors};
+
+/// Stands for the index in the paths of an array's template item.
+pub const INDEX: &str = "__index__";
+
+/// One field of a generated form.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FormField {
+    /// Where the value goes: `open_graph.images[1].url`. Also the input name.
+    pub path: String,
+    pub label: String,
+    pub description: Option<String>,
+    /// Set only when the field and everything around it must have a value,
+    /// so an optional object can be left empty as a whole.
+    pub required: bool,
+    /// The control: `text`, `number`, `checkbox`, `url`, `email`, `date`,
+    /// `datetime`, `select`, `object`, `array` or `json`, unless the
+    /// schema's `widget` names another [`crate::widgets::Widget`].
+    pub widget: String,
+    /// The schema's `widgetOptions`, e.g. the `table` of a reference.
+    pub widget_options: Map<String, Value>,
+    /// What the submitted text is read as: `string`, `integer`, `number`,
+    /// `boolean`, `variant` (a select over enum variants), `json`, or
+    /// `object` and `array` for groups.
+    pub kind: &'static str,
+    /// The current value as the control shows it.
+    pub value: String,
+    /// Extra HTML attributes from the schema: `minlength`, `maxlength`,
+    /// `pattern`, `min`, `max`, `step`.
+    pub attrs: Map<String, Value>,
+    /// A select's options, in schema order.
+    pub options: Vec<SelectOption>,
+    /// An object's properties, an array's items, or the data of a select's
+    /// variants.
+    pub fields: Vec<FormField>,
+    /// An array's blank item.
+    pub template: Option<Box<FormField>>,
+    /// Why the submitted value was rejected; see [`refill`].
+    pub error: Option<String>,
+    /// The schema's `visibleIf`, with the sibling's path.
+    pub visible_if: Option<Visibility>,
+    /// `visible_if` does not hold for the current value.
+    pub hidden: bool,
+}
+
+/// A field is shown while the control at `path` has the value `equals`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Visibility {
+    pub path: String,
+    pub equals: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SelectOption {
+    pub value: String,
+    pub label: String,
+}
+
+/// The fields of the object `root` describes, filled in from `value`
+/// (`Value::Null` for a new document).
+pub fn form(root: &RootSchema, value: &Value) -> Vec<FormField> {
+    let (schema, definitions) = split(root);
+    let walker = Walker { definitions: &definitions, errors: &[], submitted: false };
+    walker.properties(&schema, "", true, value)
+}
+
+/// The form again after [`decode`] rejected it: the author's input, with
+/// each error on the field at its path.
+pub fn refill(root: &RootSchema, rejected: &Rejected) -> Vec<FormField> {
+    let (schema, definitions) = split(root);
+    let walker = Walker { definitions: &definitions, errors: &rejected.errors.0, submitted: true };
+    walker.properties(&schema, "", true, &rejected.input)
+}
+
+/// Removes what the `visibleIf` conditions of `root` hide from `value`, for
+/// writes that do not come through [`decode`], such as the JSON API.
+pub fn clear_hidden(root: &RootSchema, value: &mut Value) {
+    let (schema, definitions) = split(root);
+    let walker = Walker { definitions: &definitions, errors: &[], submitted: false };
+    walker.clear(&schema, value);
+}
+
+/// A submission [`decode`] could not turn into a valid value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejected {
+    /// What was entered, shaped like the value: text at every leaf, arrays
+    /// renumbered from 0 so the paths of `errors` match [`refill`]'s.
+    pub input: Value,
+    pub errors: ValidationErrors,
+}
+
+/// Reads submitted `(name, text)` pairs, named by path as [`form`] names
+/// its inputs, as the `T` that `root` describes, then validates it.
+///
+/// Text is read by the schema: numbers, booleans, URLs, `datetime-local`
+/// times and enum variants, with a message at the field's path when it
+/// does not parse. Blank fields are left out, and an optional object or
+/// array with nothing entered in it is left out whole; array items are
+/// kept in index order, however sparse.
+pub fn decode<T: DeserializeOwned + Validate>(root: &RootSchema, pairs: &[(String, String)]) -> Result<T, Rejected> {
+    let (schema, definitions) = split(root);
+    let walker = Walker { definitions: &definitions, errors: &[], submitted: true };
+    let mut errors = Vec::new();
+    let (input, value) = walker.read(&schema, "", true, &tree(pairs), &mut errors);
+
+    let decoded = if errors.is_empty() {
+        serde_json::from_value::<T>(value.unwrap_or(Value::Null))
+            .map_err(|e| ValidationErrors(vec![field_error("", &e.to_string())]))
+            .and_then(|document| document.validate().map(|()| document))
+    } else {
+        Err(ValidationErrors(errors))
+    };
+    decoded.map_err(|errors| Rejected { input, errors })
+}
+
+/// The root schema as a value, and its definitions.
+fn split(root: &RootSchema) -> (Value, Map<String, Value>) {
+    let Ok(Value::Object(schema)) = serde_json::to_value(root) else {
+        return (Value::Null, Map::new());
+    };
+    let definitions = match schema.get("definitions") {
+        Some(Value::Object(definitions)) => definitions.clone(),
+        _ => Map::new(),
+    };
+    (Value::Object(schema), definitions)
+}
+
+struct Walker<'a> {
+    definitions: &'a Map<String, Value>,
+    /// Shown on the fields at their paths.
+    errors: &'a [FieldError],
+    /// The values are text as submitted, not the document's.
+    submitted: bool,
+}
+
+impl Walker<'_> {
+    /// Follows a `$ref`, or the one schema of an `allOf`, which is how
+    /// `schemars` gives a `$ref` a description or a default. The referring
+    /// schema's own `title`, `description`, `default` and widget are kept.
+    fn resolve(&self, schema: &Value) -> Value {
+        let target = match (schema.get("$ref").and_then(Value::as_str), schema.get("allOf")) {
+            (Some(reference), _) => match reference.strip_prefix("#/definitions/") {
+                Some(name) => self.definitions.get(name).unwrap_or(&Value::Bool(true)),
+                None => return schema.clone(),
+            },
+            (None, Some(Value::Array(all_of))) if all_of.len() == 1 => &all_of[0],
+            _ => return schema.clone(),
+        };
+        let mut resolved = self.resolve(target);
+        copy_metadata(schema, &mut resolved);
+        resolved
+    }
+
+    /// The schema without its `null` alternative, and whether it had one.
+    fn non_null(&self, schema: &Value) -> (Value, bool) {
+        let schema = self.resolve(schema);
+        if let Some(Value::Array(any_of)) = schema.get("anyOf") {
+            let others: Vec<&Value> = any_of.iter().filter(|s| s.get("type") != Some(&Value::from("null"))).collect();
+            if let ([only], true) = (others.as_slice(), others.len() < any_of.len()) {
+                let mut inner = self.resolve(only);
+                copy_metadata(&schema, &mut inner);
+                return (inner, true);
+            }
+        }
+        if let Some(Value::Array(types)) = schema.get("type") {
+            let others: Vec<&Value> = types.iter().filter(|t| *t != "null").collect();
+            if others.len() < types.len() {
+                let mut schema = schema.clone();
+                schema["type"] = match others.as_slice() {
+                    [only] => (*only).clone(),
+                    _ => Value::Array(others.into_iter().cloned().collect()),
+                };
+                return (schema, true);
+            }
+        }
+        (schema, false)
+    }
+
+    fn properties(&self, schema: &Value, path: &str, required: bool, value: &Value) -> Vec<FormField> {
+        let Some(Value::Object(properties)) = schema.get("properties") else {
+            return Vec::new();
+        };
+        let required_names: Vec<&str> = match schema.get("required") {
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        properties
+            .iter()
+            .map(|(name, property)| {
+                let path_of = |name: &str| if path.is_empty() { name.to_string() } else { format!("{}.{}", path, name) };
+                let required = required && required_names.contains(&name.as_str());
+                let mut field =
+                    self.field(property, path_of(name), humanize(name), required, value.get(name).unwrap_or(&Value::Null));
+                if let Some((when, equals)) = condition(property) {
+                    field.visible_if = Some(Visibility { path: path_of(when), equals: equals.to_string() });
+                    field.hidden = hidden(property, value);
+                }
+                field
+            })
+            .collect()
+    }
+
+    /// Drops hidden properties, at any depth; see [`clear_hidden`].
+    fn clear(&self, schema: &Value, value: &mut Value) {
+        let (schema, _) = self.non_null(schema);
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (name, property) in properties {
+                if hidden(property, value) {
+                    if let Value::Object(object) = value {
+                        object.remove(name);
+                    }
+                } else if let Some(child) = value.get_mut(name) {
+                    self.clear(property, child);
+                }
+            }
+        } else if let (Some(items), Value::Array(values)) = (schema.get("items"), &mut *value) {
+            for item in values {
+                self.clear(items, item);
+            }
+        } else if let (Some(Value::Array(variants)), Value::Object(object)) = (schema.get("oneOf"), &mut *value) {
+            for (name, data) in object {
+                let variant = variants.iter().map(|v| self.resolve(v)).find_map(|v| v.get("properties")?.get(name).cloned());
+                if let Some(variant) = variant {
+                    self.clear(&variant, data);
+                }
+            }
+        }
+    }
+
+    fn field(&self, schema: &Value, path: String, label: String, required: bool, value: &Value) -> FormField {
+        let (schema, nullable) = self.non_null(schema);
+        let required = required && !nullable && schema.get("default").is_none();
+        let mut field = FormField {
+            label: schema.get("title").and_then(Value::as_str).map_or(label, str::to_string),
+            description: schema.get("description").and_then(Value::as_str).map(str::to_string),
+            path,
+            required,
+            widget: "text".into(),
+            widget_options: Map::new(),
+            kind: "string",
+            value: String::new(),
+            attrs: Map::new(),
+            options: Vec::new(),
+            fields: Vec::new(),
+            template: None,
+            error: None,
+            visible_if: None,
+            hidden: false,
+        };
+
+        let types = type_of(&schema);
+        if schema.get("enum").is_some() || schema.get("oneOf").is_some() {
+            self.select(&mut field, &schema, value);
+        } else if types == ["object"] && schema.get("properties").is_some() {
+            field.widget = "object".into();
+            field.kind = "object";
+            field.fields = self.properties(&schema, &field.path, required, value);
+        } else if types == ["array"] {
+            self.array(&mut field, &schema, value);
+        } else if types == ["boolean"] {
+            self.boolean(&mut field, value);
+        } else if types == ["integer"] || types == ["number"] {
+            field.widget = "number".into();
+            field.kind = if types == ["integer"] { "integer" } else { "number" };
+            field.value = text(value);
+            for (key, attr) in [("minimum", "min"), ("maximum", "max")] {
+                if let Some(bound) = schema.get(key).and_then(Value::as_f64) {
+                    field.attrs.insert(attr.into(), number(bound));
+                }
+            }
+            field.attrs.insert("step".into(), if field.kind == "integer" { 1.into() } else { "any".into() });
+        } else if types == ["string"] {
+            self.string(&mut field, &schema, value);
+        } else {
+            field.widget = "json".into();
+            field.kind = "json";
+            field.value = match value {
+                Value::Null => String::new(),
+                Value::String(entered) if self.submitted => entered.clone(),
+                value => serde_json::to_string_pretty(value).unwrap_or_default(),
+            };
+        }
+
+        if let Some(widget) = schema.get("widget").and_then(Value::as_str) {
+            field.widget = widget.to_string();
+        }
+        if let Some(Value::Object(options)) = schema.get("widgetOptions") {
+            field.widget_options = options.clone();
+        }
+        field.error = self.errors.iter().find(|e| e.path == field.path).map(|e| e.message.clone());
+        field
+    }
+
+    fn string(&self, field: &mut FormField, schema: &Value, value: &Value) {
+        field.value = text(value);
+        field.widget = match schema.get("format").and_then(Value::as_str) {
+            Some("uri") => "url",
+            Some("email") => "email",
+            Some("date") => "date",
+            Some("date-time") => "datetime",
+            _ => "text",
+        }
+        .into();
+        for (key, attr) in [("minLength", "minlength"), ("maxLength", "maxlength"), ("pattern", "pattern")] {
+            if let Some(v) = schema.get(key) {
+                field.attrs.insert(attr.into(), v.clone());
+            }
+        }
+        if field.widget == "datetime" {
+            field.value = datetime_local(&field.value);
+            for (key, attr) in [("formatMinimum", "min"), ("formatMaximum", "max")] {
+                if let Some(bound) = schema.get(key).and_then(Value::as_str) {
+                    field.attrs.insert(attr.into(), datetime_local(bound).into());
+                }
+            }
+        }
+    }
+
+    /// A checkbox when the value must be there; otherwise a select, so
+    /// "not set" stays possible.
+    fn boolean(&self, field: &mut FormField, value: &Value) {
+        field.kind = "boolean";
+        field.value = text(value);
+        if field.required {
+            field.widget = "checkbox".into();
+        } else {
+            field.widget = "select".into();
+            field.options = vec![option("true", "Yes"), option("false", "No")];
+        }
+    }
+
+    fn select(&self, field: &mut FormField, schema: &Value, value: &Value) {
+        field.widget = "select".into();
+        field.kind = "variant";
+        let variants = match schema.get("oneOf") {
+            Some(Value::Array(variants)) => variants.iter().map(|v| self.resolve(v)).collect(),
+            _ => vec![schema.clone()],
+        };
+        for variant in &variants {
+            let title = variant.get("title").and_then(Value::as_str);
+            if let Some(Value::Array(values)) = variant.get("enum") {
+                // A nullable enum lists `null`; the blank option stands for it.
+                for v in values.iter().filter(|v| !v.is_null()) {
+                    let v = text(v);
+                    field.options.push(option(&v, title.unwrap_or(&v)));
+                }
+            } else if let Some(Value::Object(properties)) = variant.get("properties") {
+                for (name, data) in properties {
+                    field.options.push(option(name, title.unwrap_or(name)));
+                    let path = format!("{}.{}", field.path, name);
+                    let data_value = value.get(name).unwrap_or(&Value::Null);
+                    field.fields.push(self.field(data, path, humanize(name), false, data_value));
+                }
+            }
+        }
+        field.value = selected(value);
+    }
+
+    fn array(&self, field: &mut FormField, schema: &Value, value: &Value) {
+        field.widget = "array".into();
+        field.kind = "array";
+        let items = schema.get("items").cloned().unwrap_or(Value::Bool(true));
+        let item_label = |i: &str| format!("{} {}", field.label, i);
+        field.fields = match value {
+            Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| self.field(&items, format!("{}[{}]", field.path, i), item_label(&(i + 1).to_string()), true, v))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let template = self.field(&items, format!("{}[{}]", field.path, INDEX), field.label.clone(), true, &Value::Null);
+        field.template = Some(Box::new(template));
+    }
+}
+
+/// Reading submitted text back, for [`decode`]. Each reader returns the
+/// input as [`refill`] shows it and the typed value, `None` when nothing
+/// was entered, and adds an error for each field that cannot be read.
+impl Walker<'_> {
+    fn read(
+        &self,
+        schema: &Value,
+        path: &str,
+        required: bool,
+        input: &Value,
+        errors: &mut Vec<FieldError>,
+    ) -> (Value, Option<Value>) {
+        let (schema, nullable) = self.non_null(schema);
+        let required = required && !nullable && schema.get("default").is_none();
+        let types = type_of(&schema);
+        if schema.get("enum").is_some() || schema.get("oneOf").is_some() {
+            return self.read_variant(&schema, path, required, input, errors);
+        }
+        if types == ["object"] && schema.get("properties").is_some() {
+            return self.read_object(&schema, path, required, input, errors);
+        }
+        if types == ["array"] {
+            return self.read_array(&schema, path, required, input, errors);
+        }
+
+        let entered = match input {
+            Value::String(entered) => entered.as_str(),
+            _ => "",
+        };
+        let trimmed = entered.trim();
+        if trimmed.is_empty() {
+            // An unchecked checkbox sends nothing.
+            if types == ["boolean"] && required {
+                return (Value::Null, Some(Value::Bool(false)));
+            }
+            if required {
+                errors.push(field_error(path, "is required"));
+            }
+            return (Value::Null, None);
+        }
+
+        let value = if types == ["boolean"] {
+            form_input::boolean(trimmed).map(Value::Bool)
+        } else if types == ["integer"] {
+            form_input::integer(trimmed).and_then(|n| bounded(&schema, n as f64).map(|()| Value::from(n)))
+        } else if types == ["number"] {
+            form_input::number(trimmed).and_then(|n| bounded(&schema, n).map(|()| Value::from(n)))
+        } else if types == ["string"] {
+            read_string(&schema, entered, trimmed)
+        } else {
+            form_input::json(trimmed)
+        };
+        match value {
+            Ok(value) => (input.clone(), Some(value)),
+            Err(message) => {
+                errors.push(field_error(path, &message));
+                (input.clone(), None)
+            }
+        }
+    }
+
+    /// An optional object with nothing entered is left out, along with the
+    /// errors of its required fields. So are hidden properties, entered or
+    /// not.
+    fn read_object(
+        &self,
+        schema: &Value,
+        path: &str,
+        required: bool,
+        input: &Value,
+        errors: &mut Vec<FieldError>,
+    ) -> (Value, Option<Value>) {
+        let Some(Value::Object(properties)) = schema.get("properties") else {
+            return (Value::Null, None);
+        };
+        let required_names: Vec<&str> = match schema.get("required") {
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+
+        let mut own_errors = Vec::new();
+        let mut shown = Map::new();
+        let mut value = Map::new();
+        for (name, property) in properties {
+            if hidden(property, input) {
+                continue;
+            }
+            let path = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+            let required = required_names.contains(&name.as_str());
+            let input = input.get(name).unwrap_or(&Value::Null);
+            let (input, read) = self.read(property, &path, required, input, &mut own_errors);
+            if !input.is_null() {
+                shown.insert(name.clone(), input);
+            }
+            if let Some(read) = read {
+                value.insert(name.clone(), read);
+            }
+        }
+
+        let shown = Value::Object(shown);
+        if !required && !entered(&shown) {
+            return (shown, None);
+        }
+        errors.append(&mut own_errors);
+        (shown, Some(Value::Object(value)))
+    }
+
+    /// Items come in index order, renumbered from 0; blank ones are dropped.
+    fn read_array(
+        &self,
+        schema: &Value,
+        path: &str,
+        required: bool,
+        input: &Value,
+        errors: &mut Vec<FieldError>,
+    ) -> (Value, Option<Value>) {
+        let items_schema = schema.get("items").cloned().unwrap_or(Value::Bool(true));
+        let items: Vec<&Value> = match input {
+            Value::Array(items) => items.iter().collect(),
+            Value::Object(indexed) => {
+                let mut indexed: Vec<(u64, &Value)> =
+                    indexed.iter().filter_map(|(i, item)| Some((i.parse().ok()?, item))).collect();
+                indexed.sort_by_key(|(i, _)| *i);
+                indexed.into_iter().map(|(_, item)| item).collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let mut shown = Vec::new();
+        let mut value = Vec::new();
+        for item in items.into_iter().filter(|item| entered(item)) {
+            let path = format!("{}[{}]", path, shown.len());
+            let (input, read) = self.read(&items_schema, &path, true, item, errors);
+            shown.push(input);
+            value.extend(read);
+        }
+        if shown.is_empty() && !required {
+            return (Value::Null, None);
+        }
+        (Value::Array(shown), Some(Value::Array(value)))
+    }
+
+    /// The select's text names the variant; a variant with data reads it
+    /// from the field at `path.Variant`.
+    fn read_variant(
+        &self,
+        schema: &Value,
+        path: &str,
+        required: bool,
+        input: &Value,
+        errors: &mut Vec<FieldError>,
+    ) -> (Value, Option<Value>) {
+        let selected = match input {
+            Value::Object(object) => object.get("").map(text).unwrap_or_default(),
+            other => text(other),
+        };
+        let selected = selected.trim();
+        if selected.is_empty() {
+            if required {
+                errors.push(field_error(path, "is required"));
+            }
+            return (input.clone(), None);
+        }
+
+        let variants = match schema.get("oneOf") {
+            Some(Value::Array(variants)) => variants.iter().map(|v| self.resolve(v)).collect(),
+            _ => vec![schema.clone()],
+        };
+        for variant in &variants {
+            if let Some(Value::Array(values)) = variant.get("enum") {
+                if let Some(value) = values.iter().find(|v| text(v) == selected) {
+                    return (input.clone(), Some(value.clone()));
+                }
+            } else if let Some(data) = variant.get("properties").and_then(|p| p.get(selected)) {
+                let data_path = format!("{}.{}", path, selected);
+                let data_input = input.get(selected).unwrap_or(&Value::Null);
+                let (data_input, data) = self.read(data, &data_path, true, data_input, errors);
+                let mut shown = match input {
+                    Value::Object(object) => object.clone(),
+                    _ => Map::new(),
+                };
+                shown.insert(String::new(), selected.into());
+                shown.insert(selected.into(), data_input);
+                let mut value = Map::new();
+                value.extend(data.map(|data| (selected.to_string(), data)));
+                return (Value::Object(shown), Some(Value::Object(value)));
+            }
+        }
+        errors.push(field_error(path, "is not one of the choices"));
+        (input.clone(), None)
+    }
+}
+
+/// Text for a `string` schema, by its `format`.
+fn read_string(schema: &Value, entered: &str, trimmed: &str) -> Result<Value, String> {
+    let text = match schema.get("format").and_then(Value::as_str) {
+        Some("uri") => form_input::url(trimmed)?,
+        Some("date") => form_input::date(trimmed)?,
+        Some("date-time") => form_input::date_time(trimmed)?,
+        _ => entered.to_string(),
+    };
+    Ok(text.into())
+}
+
+/// Checks `minimum` and `maximum`.
+fn bounded(schema: &Value, n: f64) -> Result<(), String> {
+    if let Some(min) = schema.get("minimum").and_then(Value::as_f64).filter(|min| n < *min) {
+        return Err(format!("must be at least {}", number(min)));
+    }
+    if let Some(max) = schema.get("maximum").and_then(Value::as_f64).filter(|max| n > *max) {
+        return Err(format!("must be at most {}", number(max)));
+    }
+    Ok(())
+}
+
+/// Whether any text in `input` is not blank.
+fn entered(input: &Value) -> bool {
+    match input {
+        Value::String(s) => !s.trim().is_empty(),
+        Value::Array(items) => items.iter().any(entered),
+        Value::Object(fields) => fields.values().any(entered),
+        _ => false,
+    }
+}
+
+/// The submitted pairs as a tree of text, by path: `images[1].url` sets
+/// `{"images": {"1": {"url": ...}}}`. A select whose variant has data
+/// submits text at a path that also has fields below it; the text goes
+/// under `""`.
+pub fn tree(pairs: &[(String, String)]) -> Value {
+    let mut root = Map::new();
+    for (name, text) in pairs {
+        let keys: Vec<&str> = name.split(['.', '[', ']']).filter(|k| !k.is_empty()).collect();
+        if !keys.is_empty() {
+            insert(&mut root, &keys, text);
+        }
+    }
+    Value::Object(root)
+}
+
+fn insert(node: &mut Map<String, Value>, keys: &[&str], text: &str) {
+    let [key, rest @ ..] = keys else {
+        return;
+    };
+    if rest.is_empty() {
+        match node.get_mut(*key) {
+            Some(Value::Object(below)) => {
+                below.insert(String::new(), text.into());
+            }
+            _ => {
+                node.insert(key.to_string(), text.into());
+            }
+        }
+        return;
+    }
+    let child = node.entry(key.to_string()).or_insert_with(|| Value::Object(Map::new()));
+    if !child.is_object() {
+        let mut below = Map::new();
+        below.insert(String::new(), child.take());
+        *child = Value::Object(below);
+    }
+    if let Value::Object(below) = child {
+        insert(below, rest, text);
+    }
+}
+
+/// The `field` and `equals` of a property's `visibleIf`.
+fn condition(property: &Value) -> Option<(&str, &str)> {
+    let condition = property.get("visibleIf")?;
+    Some((condition.get("field")?.as_str()?, condition.get("equals")?.as_str()?))
+}
+
+/// Whether `property` has a `visibleIf` that `parent`, the object around
+/// it, does not meet.
+fn hidden(property: &Value, parent: &Value) -> bool {
+    condition(property).is_some_and(|(when, equals)| selected(parent.get(when).unwrap_or(&Value::Null)) != equals)
+}
+
+/// The value a select shows: the text of a scalar, or the variant of an
+/// externally tagged `{ "Name": data }`. Submitted, the select's own text
+/// is under `""` beside the data.
+fn selected(value: &Value) -> String {
+    match value {
+        Value::Object(object) => match object.get("") {
+            Some(selected) => text(selected),
+            None => object.keys().next().cloned().unwrap_or_default(),
+        },
+        other => text(other),
+    }
+}
+
+fn field_error(path: &str, message: &str) -> FieldError {
+    FieldError { path: path.into(), message: message.into() }
+}
+
+fn copy_metadata(from: &Value, to: &mut Value) {
+    if let (Value::Object(from), Value::Object(to)) = (from, to) {
+        for key in ["title", "description", "widget", "widgetOptions", "default"] {
+            if let Some(v) = from.get(key) {
+                to.insert(key.into(), v.clone());
+            }
+        }
+    }
+}
+
+/// `type` as a list, `[]` when the schema has none.
+fn type_of(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn option(value: &str, label: &str) -> SelectOption {
+    SelectOption { value: value.into(), label: label.into() }
+}
+
+/// A scalar as input text; `null` is empty.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// `0.0` as `0`, so integer bounds print as integers.
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 { Value::from(n as i64) } else { Value::from(n) }
+}
+
+/// An RFC 3339 time as a `datetime-local` value, in UTC to the minute.
+fn datetime_local(value: &str) -> String {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => {
+            let utc = time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true);
+            utc[..16].to_string()
+        }
+        Err(_) => value.to_string(),
+    }
+}
+
+/// `og_type` → "Og type".
+fn humanize(name: &str) -> String {
+    let spaced = name.replace('_', " ");
+    let mut chars = spaced.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema_v2::{Author, Category, Page, SeoMetadata};
+    use schemars::{schema_for, JsonSchema};
+    use serde_json::json;
+
+    fn find<'a>(fields: &'a [FormField], path: &str) -> &'a FormField {
+        search(fields, path).unwrap_or_else(|| panic!("no field {}", path))
+    }
+
+    fn search<'a>(fields: &'a [FormField], path: &str) -> Option<&'a FormField> {
+        fields.iter().find_map(|f| {
+            if f.path == path {
+                return Some(f);
+            }
+            search(&f.fields, path).or_else(|| f.template.as_deref().and_then(|t| search(std::slice::from_ref(t), path)))
+        })
+    }
+
+    #[test]
+    fn seo_metadata_fields_follow_the_schema() {
+        let value = json!({
+            "title": "Hello",
+            "published_time": "2025-05-22T08:00:00Z",
+            "images": ["https://example.com/a.png"],
+            "robots": { "index": true, "max_snippet": 50 },
+            "open_graph": { "og_type": { "Other": "podcast" }, "images": [{ "url": "https://example.com/b.png" }] },
+            "schema_org": { "@type": "WebPage" }
+        });
+        let mut root = schema_for!(SeoMetadata);
+        crate::widgets::registry().apply::<SeoMetadata>(&mut root);
+        let fields = form(&root, &value);
+
+        let title = find(&fields, "title");
+        assert_eq!((title.widget.as_str(), title.required, title.value.as_str()), ("textarea", false, "Hello"));
+        assert_eq!(find(&fields, "canonical").widget, "url");
+        assert_eq!(find(&fields, "published_time").value, "2025-05-22T08:00");
+
+        let images = find(&fields, "images");
+        assert_eq!(images.widget, "array");
+        assert_eq!(images.fields[0].path, "images[0]");
+        assert_eq!(images.fields[0].value, "https://example.com/a.png");
+        assert_eq!(images.template.as_ref().unwrap().path, "images[__index__]");
+
+        let index = find(&fields, "robots.index");
+        assert_eq!((index.widget.as_str(), index.value.as_str()), ("select", "true"));
+        let snippet = find(&fields, "robots.max_snippet");
+        assert_eq!((snippet.kind, snippet.value.as_str()), ("integer", "50"));
+        assert_eq!(snippet.attrs["min"], 0);
+
+        let og_type = find(&fields, "open_graph.og_type");
+        assert!(!og_type.required, "inside an optional object");
+        assert_eq!(og_type.value, "Other");
+        assert_eq!(og_type.options.last().unwrap().value, "Other");
+        assert_eq!(og_type.fields[0].path, "open_graph.og_type.Other");
+        assert_eq!(og_type.fields[0].value, "podcast");
+        assert_eq!(find(&fields, "open_graph.images[0].url").value, "https://example.com/b.png");
+
+        let alternates = find(&fields, "alternates");
+        let template = alternates.template.as_ref().unwrap();
+        assert_eq!(template.widget, "object");
+        assert_eq!(template.fields[1].path, "alternates[__index__].url");
+
+        let schema_org = find(&fields, "schema_org");
+        assert_eq!(schema_org.widget, "json");
+        assert!(schema_org.value.contains("\"@type\": \"WebPage\""));
+    }
+
+    #[test]
+    fn enum_titles_label_the_options() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Film {
+            category: Category,
+        }
+        let fields = form(&schema_for!(Film), &json!({ "category": "sci-fi" }));
+        assert!(fields[0].required);
+        assert_eq!(fields[0].value, "sci-fi");
+        assert_eq!(fields[0].options, [option("sci-fi", "Sci-Fi"), option("western", "Western")]);
+    }
+
+    #[test]
+    fn documented_and_defaulted_enums_are_selects() {
+        // `Header.element` has a doc comment and `#[serde(default)]`, so
+        // `schemars` wraps its `$ref` in an `allOf`.
+        let root = schema_for!(crate::schema::Header);
+        assert!(serde_json::to_value(&root).unwrap()["properties"]["element"]["allOf"].is_array());
+        let fields = form(&root, &json!({ "content": "Hi", "element": "h3" }));
+        let element = find(&fields, "element");
+        assert_eq!((element.widget.as_str(), element.kind, element.value.as_str()), ("select", "variant", "h3"));
+        assert!(!element.required);
+        assert_eq!(element.options.first(), Some(&option("h1", "h1")));
+        assert!(element.description.as_deref().is_some_and(|d| d.starts_with("Headers saved before this")));
+
+        let header: crate::schema::Header = decode(&root, &pairs(&[("content", "Hi"), ("element", "p")])).unwrap();
+        assert_eq!(header.element, crate::schema::HTMLElement::P);
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, text)| (name.to_string(), text.to_string())).collect()
+    }
+
+    #[test]
+    fn submitted_paths_decode_into_nested_values() {
+        let page: Page = decode(
+            &schema_for!(Page),
+            &pairs(&[
+                ("metadata.title", "Hello"),
+                ("metadata.canonical", ""),
+                ("metadata.published_time", "2025-05-22T08:00"),
+                ("metadata.images[1700000000000]", "https://example.com/b.png"),
+                ("metadata.images[3]", "https://example.com/a.png"),
+                ("metadata.robots.index", "false"),
+                ("metadata.robots.max_snippet", "50"),
+                ("metadata.open_graph.og_type", "Other"),
+                ("metadata.open_graph.og_type.Other", "podcast"),
+                ("metadata.open_graph.images[0].url", "https://example.com/c.png"),
+                ("metadata.open_graph.images[0].width", "1200"),
+                ("metadata.twitter.card", ""),
+            ]),
+        )
+        .unwrap();
+
+        let metadata = serde_json::to_value(&page.metadata).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+        assert_eq!(metadata["canonical"], Value::Null);
+        assert_eq!(metadata["published_time"], "2025-05-22T08:00:00Z");
+        assert_eq!(metadata["images"], json!(["https://example.com/a.png", "https://example.com/b.png"]));
+        assert_eq!(metadata["robots"]["index"], false);
+        assert_eq!(metadata["robots"]["max_snippet"], 50);
+        assert_eq!(metadata["open_graph"]["og_type"], json!({ "Other": "podcast" }));
+        assert_eq!(metadata["open_graph"]["images"][0]["width"], 1200);
+        assert_eq!(metadata["twitter"], Value::Null, "nothing was entered in it");
+    }
+
+    #[test]
+    fn rejected_input_comes_back_with_errors_at_its_paths() {
+        let root = schema_for!(Page);
+        let rejected = decode::<Page>(
+            &root,
+            &pairs(&[
+                ("metadata.images[4]", "https://example.com/a.png"),
+                ("metadata.images[9]", "not a url"),
+                ("metadata.robots.max_snippet", "lots"),
+                ("metadata.open_graph.title", "Hello"),
+                ("metadata.twitter.player.width", "-1"),
+            ]),
+        )
+        .unwrap_err();
+
+        let errors: Vec<(&str, &str)> =
+            rejected.errors.0.iter().map(|e| (e.path.as_str(), e.message.as_str())).collect();
+        assert_eq!(
+            errors,
+            [
+                ("metadata.images[1]", "must be a URL"),
+                ("metadata.robots.max_snippet", "must be a whole number"),
+                ("metadata.open_graph.og_type", "is required"),
+                ("metadata.twitter.player.url", "is required"),
+                ("metadata.twitter.player.width", "must be at least 0"),
+                ("metadata.twitter.player.height", "is required"),
+            ]
+        );
+
+        let fields = refill(&root, &rejected);
+        let image = find(&fields, "metadata.images[1]");
+        assert_eq!((image.value.as_str(), image.error.as_deref()), ("not a url", Some("must be a URL")));
+        assert_eq!(find(&fields, "metadata.robots.max_snippet").value, "lots");
+        assert_eq!(find(&fields, "metadata.open_graph.title").value, "Hello");
+        assert_eq!(find(&fields, "metadata.open_graph.og_type").error.as_deref(), Some("is required"));
+    }
+
+    #[test]
+    fn hidden_branches_are_hidden_and_left_out() {
+        let mut root = schema_for!(Page);
+        crate::widgets::registry().apply::<Page>(&mut root);
+
+        let value = json!({ "metadata": { "open_graph": { "og_type": "Article", "article": { "section": "News" } } } });
+        let fields = form(&root, &value);
+        let article = find(&fields, "metadata.open_graph.article");
+        let condition = Visibility { path: "metadata.open_graph.og_type".into(), equals: "Article".into() };
+        assert_eq!((article.visible_if.as_ref(), article.hidden), (Some(&condition), false));
+        assert!(find(&fields, "metadata.open_graph.videos").hidden);
+        assert!(find(&fields, "metadata.twitter.player").hidden, "no card picked");
+
+        let page: Page = decode(
+            &root,
+            &pairs(&[
+                ("metadata.open_graph.og_type", "Website"),
+                ("metadata.open_graph.article.section", "News"),
+                ("metadata.open_graph.videos[0].url", "not a url"),
+                ("metadata.twitter.card", "Summary"),
+                ("metadata.twitter.player.width", "-1"),
+            ]),
+        )
+        .unwrap();
+        let open_graph = page.metadata.open_graph.unwrap();
+        assert!(open_graph.article.is_none() && open_graph.videos.is_empty());
+        assert!(page.metadata.twitter.unwrap().player.is_none());
+
+        let mut value = json!({ "metadata": { "twitter": { "card": "App", "player": { "url": "https://example.com" } } } });
+        clear_hidden(&root, &mut value);
+        assert_eq!(value, json!({ "metadata": { "twitter": { "card": "App" } } }));
+    }
+
+    #[test]
+    fn validation_rules_run_after_decoding() {
+        let name = "x".repeat(101);
+        let rejected = decode::<Author>(&schema_for!(Author), &pairs(&[("name", &name), ("bio", "Hi")])).unwrap_err();
+        assert_eq!(rejected.errors.0[0].path, "name");
+        assert_eq!(find(&refill(&schema_for!(Author), &rejected), "bio").value, "Hi");
+    }
+}
@@ -1,38 +1,32 @@
 use chrono::{DateTime, Utc};
-use schemars::{schema_for, JsonSchema, SchemaGenerator};
-use schemars::schema::{Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use surrealdb::sql::Thing;
 use url::Url;
 
+use crate::validation::Validate;
+use crate::widgets::Widgets;
 
-fn textarea_widget_schema(g: &mut SchemaGenerator) -> Schema {
-    let mut schema: SchemaObject = String::json_schema(g).into_object();
-
-    // add our extra key
-    schema
-        .extensions
-        .insert("widget".to_string(), json!("textarea"));
-
-    Schema::Object(schema)
-}
-#[allow(dead_code)]
-fn select_widget_schema(g: &mut SchemaGenerator) -> Schema {
-    let mut schema: SchemaObject = String::json_schema(g).into_object();
-    schema.extensions.insert("widget".to_string(), json!("select"));
-    Schema::Object(schema)
-}
-
-
-
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
 pub struct Page {
     #[schemars(skip)]
     pub id: Option<Thing>,
     pub metadata: SeoMetadata,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate, Widgets)]
+pub struct Author {
+    #[schemars(skip)]
+    pub id: Option<Thing>,
+    #[validate(required, length(max = 100))]
+    pub name: String,
+    #[widget(markdown)]
+    pub bio: Option<String>,
+    pub url: Option<Url>,
+    #[widget(image)]
+    pub avatar: Option<Url>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 #[allow(dead_code)]
@@ -43,12 +37,12 @@ pub enum Category {
     Western,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Widgets)]
 pub struct SeoMetadata {
     // ─── core -----------------------------------------------------------
-    #[schemars(schema_with = "textarea_widget_schema")]
+    #[widget(textarea)]
     pub title:        Option<String>,
-    #[schemars(schema_with = "textarea_widget_schema")]
+    #[widget(textarea)]
     pub description:  Option<String>,
     pub canonical:    Option<Url>,
     pub viewport:     Option<String>,
@@ -77,7 +71,7 @@ pub struct SeoMetadata {
 }
 
 // ─── TWITTER CARD ───────────────────────────────────────────────────────────
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Widgets)]
 pub struct TwitterCard {
     pub card:        Option<TwitterCardType>,
     pub site:        Option<String>,
@@ -87,6 +81,7 @@ pub struct TwitterCard {
     #[serde(default)]
     pub images:      Vec<Url>,
     pub image_alt:   Option<String>,
+    #[visible_if(card == Player)]
     pub player:      Option<TwitterPlayer>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -141,7 +136,7 @@ pub struct Hreflang {
 }
 
 // ─── OPEN GRAPH ─────────────────────────────────────────────────────────────
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Widgets)]
 pub struct OpenGraph {
     pub og_type: OgType,
     pub url: Option<Url>,
@@ -152,7 +147,9 @@ pub struct OpenGraph {
     #[serde(default)]
     pub images: Vec<OgImage>,
     #[serde(default)]
+    #[visible_if(og_type == Video)]
     pub videos: Vec<OgVideo>,
+    #[visible_if(og_type == Article)]
     pub article: Option<OgArticle>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -195,6 +192,8 @@ pub struct OgArticle {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use schemars::schema_for;
+    use serde_json::json;
     #[test]
     fn schema_contains_metadata_property() {
         let schema = schema_for!(Page);
@@ -250,18 +249,5 @@ mod tests {
         // Extra sanity: JSON object has `metadata` key with `title`
         assert_eq!(json!({"metadata": {"title": "Hello", "description":"desc","robots":{"index":true,"follow":true,"archive":true}}})["metadata"]["title"], "Hello");
     }
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        use schemars::schema_for;
-
-        #[test]
-        fn title_field_has_widget_extension() {
-            let schema = schema_for!(SeoMetadata);
-            let v      = serde_json::to_value(schema).unwrap();
-            assert_eq!(v["properties"]["title"]["widget"], "textarea");
-            assert_eq!(v["properties"]["description"]["widget"], "textarea");
-        }
-    }
 }
 
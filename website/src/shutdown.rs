@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// Coordinates a graceful stop. `main` triggers it on SIGTERM or Ctrl-C;
+/// long-lived tasks (the `/rpc` WebSockets) watch [`Shutdown::token`] and are
+/// spawned through [`Shutdown::tasks`] so `main` can wait for them to finish.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    tasks: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn tasks(&self) -> &TaskTracker {
+        &self.tasks
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    /// Waits for every tracked task, up to `grace`. Returns `false` if some
+    /// were still running when the grace period ran out.
+    pub async fn drain(&self, grace: Duration) -> bool {
+        self.tasks.close();
+        tokio::time::timeout(grace, self.tasks.wait()).await.is_ok()
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix.
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "could not listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "could not listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn drain_waits_for_tracked_tasks() {
+        let shutdown = Shutdown::default();
+        let token = shutdown.token();
+        shutdown.tasks().spawn(async move { token.cancelled().await });
+
+        assert!(!shutdown.drain(Duration::from_millis(20)).await, "task still waiting");
+        shutdown.trigger();
+        assert!(shutdown.drain(Duration::from_secs(1)).await);
+    }
+}
@@ -0,0 +1,429 @@
+//! SurrealQL schema derived from the Rust types in [`crate::schema`].
+//!
+//! `#[derive(Schema)]` (from the `schema_derive` crate) implements [`Schema`]
+//! for structs and enums; this module implements it for the leaf types and
+//! applies the result. Tables listed in [`TABLES`] are kept in sync with
+//! their types on every migration run, so their `DEFINE` statements are
+//! never written by hand. Fields a type no longer has are only reported,
+//! unless removing them is asked for ([`Stale::Remove`]).
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::db::Db;
+use crate::schema::{Counter, PostBlock, PostRecord};
+use crate::telemetry::db_call;
+
+pub use schema_derive::Schema;
+pub use surrealdb::sql::Thing;
+
+/// How generated `TableSchema::record_id` reads a document's `id` field.
+pub trait RecordId {
+    fn record_id(&self) -> Option<&Thing>;
+}
+
+impl RecordId for Thing {
+    fn record_id(&self) -> Option<&Thing> {
+        Some(self)
+    }
+}
+
+impl RecordId for Option<Thing> {
+    fn record_id(&self) -> Option<&Thing> {
+        self.as_ref()
+    }
+}
+
+/// A type that can be stored in a SurrealDB field.
+pub trait Schema {
+    /// The SurrealQL type of a value, e.g. `string`, `object` or `array<object>`.
+    fn surql_type() -> String;
+
+    /// Appends `DEFINE FIELD` statements for everything nested under `path`
+    /// (empty at the table root). Leaf types have nothing to add.
+    fn define_fields(_table: &str, _path: &str, _out: &mut Vec<String>) {}
+
+    /// Appends the paths of [`Reference`](crate::reference::Reference)s at or
+    /// under `path`, in the form `FETCH` takes.
+    fn references(_path: &str, _out: &mut Vec<String>) {}
+}
+
+/// A [`Schema`] type stored as the records of its own table.
+pub trait TableSchema: Schema {
+    const TABLE: &'static str;
+
+    /// The document's own record id, once it has been stored.
+    fn record_id(&self) -> Option<&Thing>;
+
+    /// Every reference in the document, for `FETCH`.
+    fn reference_paths() -> Vec<String> {
+        let mut out = vec![];
+        Self::references("", &mut out);
+        out
+    }
+
+    /// `DEFINE TABLE` followed by every field, parents before children.
+    fn define_table() -> Vec<String> {
+        let mut out = vec![format!("DEFINE TABLE OVERWRITE {} SCHEMAFULL;", Self::TABLE)];
+        Self::define_fields(Self::TABLE, "", &mut out);
+        out
+    }
+}
+
+/// One table whose schema comes from a Rust type.
+pub struct TableDef {
+    pub name: &'static str,
+    pub statements: fn() -> Vec<String>,
+}
+
+const fn table<T: TableSchema>() -> TableDef {
+    TableDef { name: T::TABLE, statements: T::define_table }
+}
+
+/// Every table derived from [`crate::schema`].
+pub static TABLES: &[TableDef] = &[table::<PostBlock>(), table::<PostRecord>(), table::<Counter>()];
+
+/// The statements for every table in [`TABLES`].
+pub fn statements() -> Vec<String> {
+    TABLES.iter().flat_map(|t| (t.statements)()).collect()
+}
+
+/// A field defined on a derived table that its type no longer has, such as
+/// one left by a rename. Shown as `table.field`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleField {
+    pub table: &'static str,
+    pub field: String,
+}
+
+impl StaleField {
+    pub fn remove_statement(&self) -> String {
+        format!("REMOVE FIELD {} ON TABLE {};", self.field, self.table)
+    }
+}
+
+impl fmt::Display for StaleField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.table, self.field)
+    }
+}
+
+/// What [`apply`] does with [`StaleField`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stale {
+    /// Leave them defined; the caller reports them.
+    Keep,
+    /// Remove them, as `website migrate --prune-fields` asks.
+    Remove,
+}
+
+/// The stale fields of every table in [`TABLES`].
+pub async fn stale_fields(db: &Db) -> Result<Vec<StaleField>, surrealdb::Error> {
+    let mut stale = Vec::new();
+    for table in TABLES {
+        stale.extend(stale_on(db, table).await?);
+    }
+    Ok(stale)
+}
+
+async fn stale_on(db: &Db, table: &TableDef) -> Result<Vec<StaleField>, surrealdb::Error> {
+    let derived: BTreeSet<String> = (table.statements)().iter().filter_map(|s| field_name(s)).collect();
+    let defined = defined_fields(db, table.name).await?;
+    Ok(defined
+        .into_iter()
+        .filter(|field| {
+            // SurrealDB defines `<array>.*` itself for every typed array.
+            let implicit = field.strip_suffix(".*").is_some_and(|array| derived.contains(array));
+            !implicit && !derived.contains(field)
+        })
+        .map(|field| StaleField { table: table.name, field })
+        .collect())
+}
+
+/// Defines (or redefines) every derived table and field. Returns the stale
+/// fields, which are removed in the same transaction only with
+/// [`Stale::Remove`].
+pub async fn apply(db: &Db, stale: Stale) -> Result<Vec<StaleField>, surrealdb::Error> {
+    let mut found = Vec::new();
+    for table in TABLES {
+        let mut sql = vec!["BEGIN TRANSACTION;".to_string()];
+        let fields = stale_on(db, table).await?;
+        if stale == Stale::Remove {
+            sql.extend(fields.iter().map(StaleField::remove_statement));
+        }
+        sql.extend((table.statements)());
+        sql.push("COMMIT TRANSACTION;".to_string());
+
+        db_call("define", table.name, async { db.query(sql.join("\n")).await?.check() }).await?;
+        found.extend(fields);
+    }
+    Ok(found)
+}
+
+/// Appends `DEFINE FIELD <parent>.<name>` for a `T`, then `T`'s own fields.
+pub fn define_field<T: Schema>(table: &str, parent: &str, name: &str, out: &mut Vec<String>) {
+    let path = join(parent, name);
+    out.push(define(&path, table, &T::surql_type()));
+    T::define_fields(table, &path, out);
+}
+
+/// Appends the reference paths of a `T` stored at `<parent>.<name>`.
+pub fn field_references<T: Schema>(parent: &str, name: &str, out: &mut Vec<String>) {
+    T::references(&join(parent, name), out);
+}
+
+/// An externally tagged newtype variant: `{ "<name>": T }`, present only
+/// when the value is that variant.
+pub fn define_variant<T: Schema>(table: &str, parent: &str, name: &str, out: &mut Vec<String>) {
+    let path = join(parent, name);
+    out.push(define(&path, table, &format!("option<{}>", T::surql_type())));
+    T::define_fields(table, &path, out);
+}
+
+/// An externally tagged struct variant; returns the path its fields go under.
+pub fn define_variant_object(table: &str, parent: &str, name: &str, out: &mut Vec<String>) -> String {
+    let path = join(parent, name);
+    out.push(define(&path, table, "option<object>"));
+    path
+}
+
+fn define(path: &str, table: &str, surql_type: &str) -> String {
+    format!("DEFINE FIELD OVERWRITE {} ON TABLE {} TYPE {};", path, table, surql_type)
+}
+
+pub fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+/// The field path of a `DEFINE FIELD OVERWRITE <path> ON ...` statement.
+fn field_name(statement: &str) -> Option<String> {
+    let rest = statement.strip_prefix("DEFINE FIELD OVERWRITE ")?;
+    rest.split_whitespace().next().map(str::to_string)
+}
+
+/// Field paths currently defined on `table`, written the way
+/// [`define_field`] writes them (`blocks.*.Header`, not `blocks[*].Header`).
+async fn defined_fields(db: &Db, table: &str) -> Result<Vec<String>, surrealdb::Error> {
+    let info: Option<Value> = db_call("info", table, async {
+        db.query(format!("INFO FOR TABLE {};", table)).await?.check()?.take(0)
+    })
+    .await?;
+    let fields = info
+        .as_ref()
+        .and_then(|info| info.get("fields"))
+        .and_then(Value::as_object)
+        .map(|fields| fields.keys().map(|k| k.replace("[*]", ".*")).collect())
+        .unwrap_or_default();
+    Ok(fields)
+}
+
+macro_rules! leaf {
+    ($surql:literal: $($ty:ty),+) => {
+        $(impl Schema for $ty {
+            fn surql_type() -> String {
+                $surql.to_string()
+            }
+        })+
+    };
+}
+
+leaf!("string": String, str, url::Url);
+leaf!("bool": bool);
+leaf!("int": i8, i16, i32, i64, u8, u16, u32, u64, usize, isize);
+leaf!("float": f32, f64);
+leaf!("datetime": chrono::DateTime<chrono::Utc>, surrealdb::sql::Datetime);
+leaf!("record": Thing);
+leaf!("any": Value);
+
+/// `#[serde(serialize_with = "surql::datetime")]` for a `DateTime<Utc>`
+/// stored in a `datetime` field. chrono serializes to a string, which
+/// SurrealDB will not coerce; JSON output is the same string either way.
+pub fn datetime<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    surrealdb::sql::Datetime::from(*value).serialize(serializer)
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn surql_type() -> String {
+        format!("array<{}>", T::surql_type())
+    }
+
+    fn define_fields(table: &str, path: &str, out: &mut Vec<String>) {
+        T::define_fields(table, &join(path, "*"), out);
+    }
+
+    // `FETCH blocks` resolves every link in the array; no `.*` needed.
+    fn references(path: &str, out: &mut Vec<String>) {
+        T::references(path, out);
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn surql_type() -> String {
+        format!("option<{}>", T::surql_type())
+    }
+
+    fn define_fields(table: &str, path: &str, out: &mut Vec<String>) {
+        T::define_fields(table, path, out);
+    }
+
+    fn references(path: &str, out: &mut Vec<String>) {
+        T::references(path, out);
+    }
+}
+
+impl<T: Schema + ?Sized> Schema for Box<T> {
+    fn surql_type() -> String {
+        T::surql_type()
+    }
+
+    fn define_fields(table: &str, path: &str, out: &mut Vec<String>) {
+        T::define_fields(table, path, out);
+    }
+
+    fn references(path: &str, out: &mut Vec<String>) {
+        T::references(path, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::{self, Mode};
+    use surrealdb::engine::any;
+
+    #[test]
+    fn post_schema_links_blocks() {
+        let expected = [
+            "DEFINE TABLE OVERWRITE posts SCHEMAFULL;",
+            "DEFINE FIELD OVERWRITE title ON TABLE posts TYPE string;",
+            "DEFINE FIELD OVERWRITE blocks ON TABLE posts TYPE array<record<blocks>>;",
+            "DEFINE FIELD OVERWRITE updated_at ON TABLE posts TYPE option<datetime>;",
+        ];
+        assert_eq!(PostRecord::define_table(), expected);
+        assert_eq!(PostRecord::reference_paths(), ["blocks"]);
+
+        let expected = [
+            "DEFINE TABLE OVERWRITE blocks SCHEMAFULL;",
+            "DEFINE FIELD OVERWRITE Header ON TABLE blocks TYPE option<object>;",
+            "DEFINE FIELD OVERWRITE Header.content ON TABLE blocks TYPE string;",
+            "DEFINE FIELD OVERWRITE Header.element ON TABLE blocks TYPE string;",
+            "DEFINE FIELD OVERWRITE Footer ON TABLE blocks TYPE option<object>;",
+            "DEFINE FIELD OVERWRITE Footer.copyright ON TABLE blocks TYPE string;",
+        ];
+        let statements = PostBlock::define_table();
+        assert_eq!(statements[..expected.len()], expected);
+        assert!(statements.contains(
+            &"DEFINE FIELD OVERWRITE RichText.body.*.children.*.marks ON TABLE blocks TYPE array<string>;".to_string()
+        ));
+    }
+
+    #[derive(Schema, serde::Serialize)]
+    #[allow(dead_code)]
+    enum Mixed {
+        Empty,
+        Named { count: i32, tags: Vec<String> },
+    }
+
+    #[derive(Schema, serde::Serialize)]
+    #[allow(dead_code)]
+    struct Wrapper {
+        #[serde(rename = "kind")]
+        mixed: Option<Mixed>,
+        #[schema(skip)]
+        cache: Vec<u8>,
+    }
+
+    #[test]
+    fn enums_renames_and_skips() {
+        let mut out = vec![];
+        Wrapper::define_fields("t", "", &mut out);
+        assert_eq!(
+            out,
+            [
+                "DEFINE FIELD OVERWRITE kind ON TABLE t TYPE option<string | object>;",
+                "DEFINE FIELD OVERWRITE kind.Named ON TABLE t TYPE option<object>;",
+                "DEFINE FIELD OVERWRITE kind.Named.count ON TABLE t TYPE int;",
+                "DEFINE FIELD OVERWRITE kind.Named.tags ON TABLE t TYPE array<string>;",
+            ]
+        );
+    }
+
+    #[derive(Schema, serde::Serialize)]
+    #[serde(rename_all = "snake_case", rename_all_fields = "camelCase")]
+    #[allow(dead_code)]
+    enum Renamed {
+        PageView { page_url: String },
+        #[serde(rename_all = "kebab-case")]
+        LinkClick { link_text: String },
+    }
+
+    #[derive(Schema, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct Event {
+        event_kind: Renamed,
+        #[serde(rename = "at")]
+        happened_at: String,
+    }
+
+    #[test]
+    fn rename_all_names_fields_as_serde_does() {
+        let mut out = vec![];
+        Event::define_fields("t", "", &mut out);
+        assert_eq!(
+            out,
+            [
+                "DEFINE FIELD OVERWRITE eventKind ON TABLE t TYPE object;",
+                "DEFINE FIELD OVERWRITE eventKind.page_view ON TABLE t TYPE option<object>;",
+                "DEFINE FIELD OVERWRITE eventKind.page_view.pageUrl ON TABLE t TYPE string;",
+                "DEFINE FIELD OVERWRITE eventKind.link_click ON TABLE t TYPE option<object>;",
+                "DEFINE FIELD OVERWRITE eventKind.link_click.link-text ON TABLE t TYPE string;",
+                "DEFINE FIELD OVERWRITE at ON TABLE t TYPE string;",
+            ]
+        );
+        let event = Event {
+            event_kind: Renamed::PageView { page_url: "/".into() },
+            happened_at: String::new(),
+        };
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            serde_json::json!({ "eventKind": { "page_view": { "pageUrl": "/" } }, "at": "" })
+        );
+    }
+
+    #[tokio::test]
+    async fn apply_defines_tables_and_reports_stale_fields() {
+        let db = any::connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        migrations::run(&db, Mode::Apply).await.unwrap();
+        db.query("DEFINE FIELD legacy ON TABLE posts TYPE string")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let stale = apply(&db, Stale::Keep).await.unwrap();
+        assert_eq!(stale, [StaleField { table: "posts", field: "legacy".into() }]);
+        assert_eq!(stale[0].remove_statement(), "REMOVE FIELD legacy ON TABLE posts;");
+        assert_eq!(stale_fields(&db).await.unwrap(), stale, "kept unless asked");
+
+        assert_eq!(apply(&db, Stale::Remove).await.unwrap(), stale);
+        assert!(stale_fields(&db).await.unwrap().is_empty());
+        assert!(apply(&db, Stale::Keep).await.unwrap().is_empty(), "a second run changes nothing");
+
+        let bad = db
+            .query("CREATE posts CONTENT { title: { label: 'not a string' }, blocks: [] }")
+            .await
+            .unwrap()
+            .check();
+        assert!(bad.is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! `tracing` setup: the subscriber, the per-request span and the helpers
+//! that wrap SurrealDB calls in child spans.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::HeaderName;
+use axum::response::Response;
+use tracing::{Instrument, Span};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogConfig, LogFormat};
+
+/// Set on every request that arrives without one, and echoed on the
+/// response, so a user report can be matched to its log lines.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Installs the global subscriber. `RUST_LOG`, when set, replaces
+/// `log.filter`.
+pub fn init(config: &LogConfig) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.filter))
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid log filter {:?} ({}); using \"info\".", config.filter, e);
+            EnvFilter::new("info")
+        });
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match config.format {
+        LogFormat::Pretty => builder.pretty().init(),
+        LogFormat::Json => builder.json().flatten_event(true).with_current_span(true).init(),
+    }
+}
+
+/// Span for one request. `status` and `latency_ms` are filled in by
+/// [`record_response`].
+pub fn request_span(request: &Request) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or("fallback");
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        request_id,
+        method = %request.method(),
+        route,
+        uri = %request.uri(),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+    )
+}
+
+pub fn record_response(response: &Response, latency: Duration, span: &Span) {
+    let status = response.status().as_u16();
+    let latency_ms = latency.as_secs_f64() * 1000.0;
+    span.record("status", status);
+    span.record("latency_ms", latency_ms);
+    tracing::info!(status, latency_ms, "finished request");
+}
+
+/// Runs one SurrealDB call inside a `surrealdb` child span and logs how
+/// long it took, and its error if it failed.
+pub async fn db_call<T, E, F>(op: &'static str, table: &str, call: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let span = tracing::info_span!("surrealdb", op, table);
+    async {
+        let started = Instant::now();
+        let result = call.await;
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        match &result {
+            Ok(_) => tracing::debug!(elapsed_ms, "query finished"),
+            Err(e) => tracing::warn!(elapsed_ms, error = %e, "query failed"),
+        }
+        result
+    }
+    .instrument(span)
+    .await
+}
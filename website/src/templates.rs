@@ -0,0 +1,61 @@
+use std::sync::RwLock;
+use std::time::Instant;
+
+use tera::{Context, Tera};
+
+use crate::highlight::HighlightFunction;
+use crate::json_ld::{JsonScriptFilter, VideoJsonLdFunction};
+use crate::rich_text::{RichTextFunction, SafeHrefFilter};
+use crate::widgets;
+
+/// The shared Tera instance. Behind a lock so dev mode can `full_reload` it
+/// in place while handlers keep their `Arc<AppState>`.
+pub struct Templates {
+    tera: RwLock<Tera>,
+}
+
+impl Templates {
+    /// Registers the functions and filters every template can use.
+    pub fn new(mut tera: Tera) -> Self {
+        tera.register_function("rich_text", RichTextFunction);
+        tera.register_function("highlight", HighlightFunction);
+        tera.register_function("video_json_ld", VideoJsonLdFunction);
+        tera.register_filter("safe_href", SafeHrefFilter);
+        tera.register_filter("json_script", JsonScriptFilter);
+        Self { tera: RwLock::new(tera) }
+    }
+
+    /// The templates under `glob`, and [`widgets::TEMPLATE`] generated from
+    /// the widget registry. It is added through `extend`, so it survives
+    /// [`Templates::reload`].
+    pub fn load(glob: &str) -> tera::Result<Self> {
+        let mut generated = Tera::default();
+        generated.add_raw_template(widgets::TEMPLATE, &widgets::registry().macros())?;
+        let mut tera = Tera::parse(glob)?;
+        tera.extend(&generated)?;
+        tera.build_inheritance_chains()?;
+        tera.check_macro_files()?;
+        Ok(Self::new(tera))
+    }
+
+    pub fn render(&self, template: &str, context: &Context) -> tera::Result<String> {
+        let _span = tracing::info_span!("render", template).entered();
+        let started = Instant::now();
+        let result = self.tera.read().unwrap().render(template, context);
+        tracing::debug!(elapsed_ms = started.elapsed().as_secs_f64() * 1000.0, "template rendered");
+        result
+    }
+
+    /// Re-reads every template from disk. On a parse error the previous set
+    /// is kept, so a typo does not take the site down.
+    pub fn reload(&self) -> tera::Result<()> {
+        let mut fresh = self.tera.read().unwrap().clone();
+        fresh.full_reload()?;
+        *self.tera.write().unwrap() = fresh;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tera.read().unwrap().get_template_names().next().is_none()
+    }
+}
@@ -0,0 +1,323 @@
+//! Field validation, declared next to the field with `#[validate(...)]`:
+//! the derive turns the attribute into [`Rule`]s that writes are checked
+//! against, `schemars` reads the same attribute for the JSON Schema, and
+//! [`json_schema`] adds what it cannot express.
+//!
+//! Like HTML form validation, only `required` looks at empty strings; the
+//! other rules apply once there is a value.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use schemars::JsonSchema;
+use schemars::schema::{InstanceType, RootSchema, Schema, SchemaObject};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::fields::{Definitions, FieldDef};
+
+pub use schema_derive::Validate;
+
+/// One rule of a field, as written in `#[validate(...)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// A value that is not blank; for arrays, at least one item.
+    Required,
+    /// Characters for strings, items for arrays.
+    Length { min: Option<usize>, max: Option<usize> },
+    /// A regular expression; the derive rejects invalid ones at compile
+    /// time, and each is compiled once when first checked.
+    Pattern { pattern: &'static str },
+    /// An absolute `http` or `https` URL.
+    Url,
+    /// RFC 3339 bounds, both inclusive.
+    DateRange { min: Option<&'static str>, max: Option<&'static str> },
+}
+
+/// A field that failed a rule, e.g. `blocks[2].Video.content_url`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{} {}", error.path, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// A type whose fields have rules, usually `#[derive(Validate)]`.
+pub trait Validate {
+    /// Adds an error for every failing field, with paths under `path`.
+    fn validate_into(&self, path: &str, errors: &mut Vec<FieldError>);
+
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+        self.validate_into("", &mut errors);
+        if errors.is_empty() { Ok(()) } else { Err(ValidationErrors(errors)) }
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_into(&self, path: &str, errors: &mut Vec<FieldError>) {
+        for (i, item) in self.iter().enumerate() {
+            item.validate_into(&format!("{}[{}]", path, i), errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate_into(&self, path: &str, errors: &mut Vec<FieldError>) {
+        if let Some(value) = self {
+            value.validate_into(path, errors);
+        }
+    }
+}
+
+/// Checks `value` against `rules` in order and records the first failure.
+pub fn check<T: Check + ?Sized>(value: &T, rules: &[Rule], path: &str, errors: &mut Vec<FieldError>) {
+    if let Some(message) = rules.iter().find_map(|rule| value.check(rule).err()) {
+        errors.push(FieldError { path: path.to_string(), message });
+    }
+}
+
+/// A value rules can be checked against. Rules that do not apply to the
+/// type pass.
+pub trait Check {
+    fn check(&self, rule: &Rule) -> Result<(), String>;
+}
+
+impl Check for str {
+    fn check(&self, rule: &Rule) -> Result<(), String> {
+        if self.trim().is_empty() {
+            return if *rule == Rule::Required { Err("is required".into()) } else { Ok(()) };
+        }
+        match *rule {
+            Rule::Length { min, max } => {
+                let len = self.chars().count();
+                match (min, max) {
+                    (Some(min), _) if len < min => Err(format!("must be at least {} characters", min)),
+                    (_, Some(max)) if len > max => Err(format!("must be at most {} characters", max)),
+                    _ => Ok(()),
+                }
+            }
+            Rule::Pattern { pattern } if regex(pattern).is_match(self) => Ok(()),
+            Rule::Pattern { pattern } => Err(format!("must match {}", pattern)),
+            Rule::Url => match url::Url::parse(self) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+                _ => Err("must be an http or https URL".into()),
+            },
+            Rule::Required | Rule::DateRange { .. } => Ok(()),
+        }
+    }
+}
+
+/// `pattern`, compiled the first time it is checked.
+///
+/// # Panics
+///
+/// If `pattern` is not a valid regular expression, which only a `Rule`
+/// written by hand can be.
+fn regex(pattern: &'static str) -> Regex {
+    static COMPILED: OnceLock<Mutex<HashMap<&'static str, Regex>>> = OnceLock::new();
+    let mut compiled = COMPILED.get_or_init(Default::default).lock().unwrap();
+    compiled
+        .entry(pattern)
+        .or_insert_with(|| Regex::new(pattern).unwrap_or_else(|e| panic!("invalid pattern {}: {}", pattern, e)))
+        .clone()
+}
+
+impl Check for String {
+    fn check(&self, rule: &Rule) -> Result<(), String> {
+        self.as_str().check(rule)
+    }
+}
+
+impl<T: Check> Check for Option<T> {
+    fn check(&self, rule: &Rule) -> Result<(), String> {
+        match self {
+            Some(value) => value.check(rule),
+            None if *rule == Rule::Required => Err("is required".into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Check for Vec<T> {
+    fn check(&self, rule: &Rule) -> Result<(), String> {
+        match *rule {
+            Rule::Required if self.is_empty() => Err("needs at least one item".into()),
+            Rule::Length { min: Some(min), .. } if self.len() < min => {
+                Err(format!("must have at least {} item{}", min, if min == 1 { "" } else { "s" }))
+            }
+            Rule::Length { max: Some(max), .. } if self.len() > max => {
+                Err(format!("must have at most {} item{}", max, if max == 1 { "" } else { "s" }))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Check for DateTime<Utc> {
+    fn check(&self, rule: &Rule) -> Result<(), String> {
+        let Rule::DateRange { min, max } = *rule else {
+            return Ok(());
+        };
+        let bound = |b: &str| DateTime::parse_from_rfc3339(b).map(|d| d.with_timezone(&Utc)).ok();
+        match (min, max) {
+            (Some(min), _) if bound(min).is_some_and(|min| *self < min) => Err(format!("must be on or after {}", min)),
+            (_, Some(max)) if bound(max).is_some_and(|max| *self > max) => Err(format!("must be on or before {}", max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The JSON Schema of `T`, with the rules `schemars` does not read added
+/// to the properties of every type in `definitions`: a `required` string
+/// gets `minLength: 1`, and a date range becomes `formatMinimum` /
+/// `formatMaximum`.
+pub fn json_schema<T: JsonSchema>(definitions: &Definitions) -> RootSchema {
+    let mut root = schemars::schema_for!(T);
+    if let Some(fields) = definitions.get(&T::schema_name()) {
+        add_rules(&mut root.schema, fields);
+    }
+    for (name, schema) in &mut root.definitions {
+        if let (Schema::Object(object), Some(fields)) = (schema, definitions.get(name)) {
+            add_rules(object, fields);
+        }
+    }
+    root
+}
+
+fn add_rules(object: &mut SchemaObject, fields: &[FieldDef]) {
+    let Some(properties) = object.object.as_mut().map(|o| &mut o.properties) else {
+        return;
+    };
+    for field in fields {
+        let Some(Schema::Object(property)) = properties.get_mut(field.name) else {
+            continue;
+        };
+        for rule in field.rules {
+            match *rule {
+                Rule::Required if property.has_type(InstanceType::String) => {
+                    let string = property.string();
+                    string.min_length = Some(string.min_length.unwrap_or(0).max(1));
+                }
+                Rule::DateRange { min, max } => {
+                    if let Some(min) = min {
+                        property.extensions.insert("formatMinimum".into(), json!(min));
+                    }
+                    if let Some(max) = max {
+                        property.extensions.insert("formatMaximum".into(), json!(max));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::Fields;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, JsonSchema, Fields, Validate)]
+    struct Event {
+        #[validate(required, length(max = 10))]
+        name: String,
+        #[validate(url)]
+        link: Option<String>,
+        #[validate(regex(pattern = "^[a-z]+$"))]
+        slug: String,
+        #[validate(date_range(min = "2000-01-01T00:00:00Z"))]
+        starts: DateTime<Utc>,
+        #[validate(length(min = 1, max = 2), nested)]
+        speakers: Vec<Speaker>,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema, Fields, Validate)]
+    struct Speaker {
+        #[validate(required)]
+        name: String,
+    }
+
+    fn event() -> Event {
+        Event {
+            name: "RustConf".into(),
+            link: Some("https://rustconf.com".into()),
+            slug: "rustconf".into(),
+            starts: "2025-09-02T09:00:00Z".parse().unwrap(),
+            speakers: vec![Speaker { name: "Ferris".into() }],
+        }
+    }
+
+    #[test]
+    fn valid_values_pass() {
+        assert_eq!(event().validate(), Ok(()));
+    }
+
+    #[test]
+    fn every_failing_field_is_reported_by_path() {
+        let event = Event {
+            name: "A very long name".into(),
+            link: Some("javascript:alert(1)".into()),
+            slug: "Rust Conf".into(),
+            starts: "1999-12-31T23:59:59Z".parse().unwrap(),
+            speakers: vec![Speaker { name: " ".into() }, Speaker { name: "Ferris".into() }, Speaker { name: "".into() }],
+        };
+        let errors = event.validate().unwrap_err().0;
+        let messages: Vec<String> = errors.iter().map(|e| format!("{} {}", e.path, e.message)).collect();
+        assert_eq!(
+            messages,
+            [
+                "name must be at most 10 characters",
+                "link must be an http or https URL",
+                "slug must match ^[a-z]+$",
+                "starts must be on or after 2000-01-01T00:00:00Z",
+                "speakers must have at most 2 items",
+                "speakers[0].name is required",
+                "speakers[2].name is required",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_optional_values_skip_all_but_required() {
+        let event = Event { name: "".into(), link: None, slug: "".into(), ..event() };
+        let errors = event.validate().unwrap_err().0;
+        assert_eq!(errors, [FieldError { path: "name".into(), message: "is required".into() }]);
+    }
+
+    #[test]
+    fn json_schema_carries_the_rules() {
+        let definitions = Definitions::default().with::<Event>().with::<Speaker>();
+        let schema = serde_json::to_value(json_schema::<Event>(&definitions)).unwrap();
+        let properties = &schema["properties"];
+        assert_eq!(properties["name"]["minLength"], 1);
+        assert_eq!(properties["name"]["maxLength"], 10);
+        assert_eq!(properties["link"]["format"], "uri");
+        assert_eq!(properties["slug"]["pattern"], "^[a-z]+$");
+        assert_eq!(properties["starts"]["formatMinimum"], "2000-01-01T00:00:00Z");
+        assert_eq!(properties["speakers"]["minItems"], 1);
+        assert_eq!(schema["definitions"]["Speaker"]["properties"]["name"]["minLength"], 1);
+    }
+}
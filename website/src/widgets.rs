@@ -0,0 +1,442 @@
+//! The controls of the generic editor (see [`crate::schema_form`]), kept in
+//! a [`WidgetRegistry`]. A [`Widget`] is everything about one control: how
+//! it extends a field's JSON Schema and the Tera macro that renders it. The
+//! registry turns the macros into `macros/widgets.html`, whose `control`
+//! macro renders a field with the widget it names.
+//!
+//! The schema picks a widget for each field (`url` for a URL, `select` for
+//! an enum, ...); a field picks its own with `#[derive(Widgets)]`:
+//!
+//! ```ignore
+//! #[derive(JsonSchema, Widgets)]
+//! pub struct Author {
+//!     #[widget(markdown)]
+//!     pub bio: Option<String>,
+//!     #[widget(reference(table = "pages"))]
+//!     pub home: Option<String>,
+//! }
+//! ```
+//!
+//! and its type is added with [`WidgetRegistry::with`] in [`registry`].
+//!
+//! The same derive reads `#[visible_if(og_type == Article)]`: the field is
+//! only shown, and only kept, while its sibling `og_type` is `Article`. It
+//! becomes the field's `visibleIf` (`{ "field": "og_type", "equals":
+//! "Article" }`), which `schema_form` follows both ways. Such fields should
+//! be `Option`s, since a hidden one is left out.
+
+use std::sync::OnceLock;
+
+use schemars::schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use crate::schema_v2::{Author, OpenGraph, SeoMetadata, TwitterCard};
+
+pub use schema_derive::Widgets;
+
+/// The name of the generated template of widget macros.
+pub const TEMPLATE: &str = "macros/widgets.html";
+
+/// Options from `#[widget(name(key = "value"))]`.
+pub type Options = [(&'static str, &'static str)];
+
+/// One control of the generic editor.
+pub struct Widget {
+    /// Also the name of its macro, and the `widget` of the fields that use it.
+    pub name: &'static str,
+    /// Adds what the widget needs to the schema of a field that picked it,
+    /// such as a `format`. `widget` and `widgetOptions` are already set.
+    pub schema: fn(&mut SchemaObject, &Options),
+    /// The body of its macro, which takes a `FormField` as `field`.
+    /// `self::attrs(field=field)` writes the attributes every control has.
+    pub template: &'static str,
+}
+
+/// The widget a field picked with `#[widget(...)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldWidget {
+    /// The field's key in the stored document.
+    pub field: &'static str,
+    pub widget: &'static str,
+    pub options: &'static Options,
+}
+
+/// When a field is shown, from `#[visible_if(when == equals)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldCondition {
+    /// The field's key in the stored document.
+    pub field: &'static str,
+    /// The key of the sibling it depends on.
+    pub when: &'static str,
+    /// The value (or variant name) that sibling must have.
+    pub equals: &'static str,
+}
+
+/// A type whose fields pick widgets, or are only shown under a condition;
+/// usually `#[derive(Widgets)]`.
+pub trait Widgets {
+    fn widgets() -> Vec<FieldWidget>;
+
+    fn conditions() -> Vec<FieldCondition> {
+        Vec::new()
+    }
+}
+
+/// The registered widgets, and the fields that picked one.
+#[derive(Default)]
+pub struct WidgetRegistry {
+    widgets: Vec<Widget>,
+    /// By schema name, as in the `definitions` of a JSON Schema.
+    fields: Vec<(String, Vec<FieldWidget>, Vec<FieldCondition>)>,
+}
+
+/// Macros of the generated template that are not widgets.
+const PREAMBLE: &str = r#"{# Generated from the widget registry; see `website/src/widgets.rs`. #}
+
+{% macro attrs(field) %} id="field-{{ field.path }}" name="{{ field.path }}" data-kind="{{ field.kind }}"{% if field.required %} required{% endif %}{% if field.error %} aria-invalid="true" aria-describedby="error-{{ field.path }}"{% endif %}{% for name, value in field.attrs %} {{ name }}="{{ value }}"{% endfor %}{% endmacro %}
+"#;
+
+impl WidgetRegistry {
+    /// The widgets `schema_form` picks from the schema, and the editor's
+    /// richer ones: `color`, `image`, `markdown` and `reference`.
+    pub fn builtin() -> Self {
+        let input = |name, template| Widget { name, schema: |_, _| {}, template };
+        Self::default()
+            .register(input("text", r#"<input type="text"{{ self::attrs(field=field) }} value="{{ field.value }}" />"#))
+            .register(input(
+                "textarea",
+                r#"<textarea{{ self::attrs(field=field) }} rows="4">{{ field.value }}</textarea>"#,
+            ))
+            .register(input("number", r#"<input type="number"{{ self::attrs(field=field) }} value="{{ field.value }}" />"#))
+            .register(input("email", r#"<input type="email"{{ self::attrs(field=field) }} value="{{ field.value }}" />"#))
+            .register(input("date", r#"<input type="date"{{ self::attrs(field=field) }} value="{{ field.value }}" />"#))
+            .register(input(
+                "checkbox",
+                r#"<input type="checkbox"{{ self::attrs(field=field) }} value="true"{% if field.value == "true" %} checked{% endif %} />"#,
+            ))
+            .register(input(
+                "json",
+                r#"<textarea{{ self::attrs(field=field) }} rows="8">{{ field.value }}</textarea>"#,
+            ))
+            .register(Widget {
+                name: "select",
+                schema: select_schema,
+                template: r#"<select{{ self::attrs(field=field) }}>
+    {% if not field.required %}<option value="">—</option>{% endif %}
+    {% for option in field.options %}
+    <option value="{{ option.value }}"{% if option.value == field.value %} selected{% endif %}>{{ option.label }}</option>
+    {% endfor %}
+</select>"#,
+            })
+            .register(Widget {
+                name: "datetime",
+                schema: |schema, _| schema.format = Some("date-time".into()),
+                template: r#"<input type="datetime-local"{{ self::attrs(field=field) }} value="{{ field.value }}" />"#,
+            })
+            .register(Widget {
+                name: "url",
+                schema: |schema, _| schema.format = Some("uri".into()),
+                template: r#"<input type="url"{{ self::attrs(field=field) }} value="{{ field.value }}" />"#,
+            })
+            .register(Widget {
+                name: "color",
+                schema: |schema, _| schema.string().pattern = Some("^#[0-9a-fA-F]{6}$".into()),
+                template: r##"<input type="color"{{ self::attrs(field=field) }} value="{% if field.value %}{{ field.value }}{% else %}#000000{% endif %}" />"##,
+            })
+            .register(Widget {
+                name: "image",
+                schema: |schema, _| {
+                    schema.format = Some("uri".into());
+                    schema.extensions.insert("contentMediaType".into(), json!("image/*"));
+                },
+                template: r#"<input type="url"{{ self::attrs(field=field) }} value="{{ field.value }}" oninput="this.nextElementSibling.src = this.value" />
+<img class="image-preview" src="{{ field.value }}" alt="" width="160"{% if not field.value %} hidden{% endif %} onload="this.hidden = false" onerror="this.hidden = true" />"#,
+            })
+            .register(Widget {
+                name: "markdown",
+                schema: |schema, _| {
+                    schema.extensions.insert("contentMediaType".into(), json!("text/markdown"));
+                },
+                template: r#"<textarea{{ self::attrs(field=field) }} rows="12" data-widget="markdown">{{ field.value }}</textarea>"#,
+            })
+            .register(Widget {
+                // The record as `table:key`, picked from `/api/<table>`.
+                name: "reference",
+                schema: reference_schema,
+                template: r#"<input type="text"{{ self::attrs(field=field) }} value="{{ field.value }}" list="field-{{ field.path }}-records" />
+<datalist id="field-{{ field.path }}-records" data-table="{{ field.widget_options.table }}"></datalist>
+<script>
+    (async list => {
+        const table = list.dataset.table;
+        const records = await fetch(`/api/${table}`).then(res => res.json());
+        list.replaceChildren(...records.map(record => {
+            // The API writes the id as a reference does, `{ tb, id: { <kind>: key } }`.
+            const key = `${record.id.tb}:${Object.values(record.id.id)[0]}`;
+            return new Option(record.name ?? record.title ?? key, key);
+        }));
+    })(document.currentScript.previousElementSibling);
+</script>"#,
+            })
+    }
+
+    /// # Panics
+    ///
+    /// If a widget with the same name is registered, or the name is one of
+    /// the template's own macros.
+    pub fn register(mut self, widget: Widget) -> Self {
+        assert!(!matches!(widget.name, "attrs" | "control"), "`{}` is a reserved macro name", widget.name);
+        assert!(self.get(widget.name).is_none(), "the `{}` widget is registered twice", widget.name);
+        self.widgets.push(widget);
+        self
+    }
+
+    /// Adds the widgets `T`'s fields picked, and their conditions.
+    ///
+    /// # Panics
+    ///
+    /// If one of them is not registered, or a condition names a field `T`
+    /// does not have.
+    pub fn with<T: Widgets + JsonSchema>(mut self) -> Self {
+        let fields = T::widgets();
+        for field in &fields {
+            assert!(
+                self.get(field.widget).is_some(),
+                "`{}.{}` picks `{}`, which is not a registered widget",
+                T::schema_name(),
+                field.field,
+                field.widget,
+            );
+        }
+        let conditions = T::conditions();
+        let root = schemars::schema_for!(T);
+        let properties = root.schema.object.map(|o| o.properties).unwrap_or_default();
+        for condition in &conditions {
+            for name in [condition.field, condition.when] {
+                assert!(
+                    properties.contains_key(name),
+                    "`{}.{}` depends on `{}`, which is not a field",
+                    T::schema_name(),
+                    condition.field,
+                    name,
+                );
+            }
+        }
+        self.fields.push((T::schema_name(), fields, conditions));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Widget> {
+        self.widgets.iter().find(|w| w.name == name)
+    }
+
+    /// Sets `widget` (and `widgetOptions`) on every field of `T`'s schema,
+    /// and of the types in its `definitions`, that picked a widget, and
+    /// `visibleIf` on those with a condition.
+    pub fn apply<T: JsonSchema>(&self, root: &mut RootSchema) {
+        self.apply_to(&T::schema_name(), &mut root.schema);
+        for (name, schema) in &mut root.definitions {
+            if let Schema::Object(object) = schema {
+                self.apply_to(name, object);
+            }
+        }
+    }
+
+    fn apply_to(&self, schema_name: &str, object: &mut SchemaObject) {
+        let Some((_, fields, conditions)) = self.fields.iter().find(|(name, ..)| name == schema_name) else {
+            return;
+        };
+        let Some(properties) = object.object.as_mut().map(|o| &mut o.properties) else {
+            return;
+        };
+        for field in fields {
+            let (Some(Schema::Object(property)), Some(widget)) = (properties.get_mut(field.field), self.get(field.widget))
+            else {
+                continue;
+            };
+            property.extensions.insert("widget".into(), json!(field.widget));
+            if !field.options.is_empty() {
+                let options: Map<String, Value> = field.options.iter().map(|(k, v)| (k.to_string(), json!(v))).collect();
+                property.extensions.insert("widgetOptions".into(), Value::Object(options));
+            }
+            (widget.schema)(property, field.options);
+        }
+        for condition in conditions {
+            if let Some(Schema::Object(property)) = properties.get_mut(condition.field) {
+                property
+                    .extensions
+                    .insert("visibleIf".into(), json!({ "field": condition.when, "equals": condition.equals }));
+            }
+        }
+    }
+
+    /// The source of [`TEMPLATE`]: a macro per widget, and `control(field)`,
+    /// which calls the one `field.widget` names, `text` for unknown names.
+    pub fn macros(&self) -> String {
+        let mut source = PREAMBLE.to_string();
+        for widget in &self.widgets {
+            source.push_str(&format!("\n{{% macro {}(field) %}}\n{}\n{{% endmacro %}}\n", widget.name, widget.template));
+        }
+
+        source.push_str("\n{% macro control(field) %}\n");
+        for (i, widget) in self.widgets.iter().enumerate() {
+            let keyword = if i == 0 { "if" } else { "elif" };
+            source.push_str(&format!(
+                "{{% {} field.widget == \"{}\" %}}{{{{ self::{}(field=field) }}}}\n",
+                keyword, widget.name, widget.name
+            ));
+        }
+        if self.get("text").is_some() {
+            source.push_str("{% else %}{{ self::text(field=field) }}\n");
+        }
+        if !self.widgets.is_empty() {
+            source.push_str("{% endif %}\n");
+        }
+        source.push_str("{% endmacro %}\n");
+        source
+    }
+}
+
+/// `select(choices = "a, b")` limits a string to the choices.
+fn select_schema(schema: &mut SchemaObject, options: &Options) {
+    let Some((_, choices)) = options.iter().find(|(key, _)| *key == "choices") else {
+        return;
+    };
+    let mut values: Vec<Value> = choices.split(',').map(|c| json!(c.trim())).collect();
+    let nullable = match &schema.instance_type {
+        Some(SingleOrVec::Vec(types)) => types.contains(&InstanceType::Null),
+        Some(SingleOrVec::Single(t)) => **t == InstanceType::Null,
+        None => false,
+    };
+    if nullable {
+        values.push(Value::Null);
+    }
+    schema.enum_values = Some(values);
+}
+
+/// `reference(table = "authors")` takes `authors:<key>`; without a table,
+/// any `table:key`.
+fn reference_schema(schema: &mut SchemaObject, options: &Options) {
+    let table = match options.iter().find(|(key, _)| *key == "table") {
+        Some((_, table)) => regex::escape(table),
+        None => "[^:]+".into(),
+    };
+    schema.string().pattern = Some(format!("^{table}:.+$"));
+}
+
+/// Every widget the editor has, and the types whose fields pick one; built
+/// on first use.
+pub fn registry() -> &'static WidgetRegistry {
+    static REGISTRY: OnceLock<WidgetRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        WidgetRegistry::builtin()
+            .with::<SeoMetadata>()
+            .with::<OpenGraph>()
+            .with::<TwitterCard>()
+            .with::<Author>()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schemars::schema_for;
+
+    fn schema<T: JsonSchema>() -> Value {
+        let mut root = schema_for!(T);
+        registry().apply::<T>(&mut root);
+        serde_json::to_value(root).unwrap()
+    }
+
+    #[test]
+    fn picked_widgets_extend_the_field_schema() {
+        let seo = schema::<SeoMetadata>();
+        assert_eq!(seo["properties"]["title"]["widget"], "textarea");
+        assert_eq!(seo["properties"]["title"]["type"], json!(["string", "null"]));
+        assert!(seo["required"].as_array().is_none_or(|r| r.is_empty()), "Options stay optional");
+
+        let author = schema::<Author>();
+        assert_eq!(author["properties"]["bio"]["contentMediaType"], "text/markdown");
+        assert_eq!(author["properties"]["avatar"]["widget"], "image");
+        assert_eq!(author["properties"]["avatar"]["format"], "uri");
+    }
+
+    #[test]
+    fn options_reach_the_schema() {
+        #[derive(JsonSchema, Widgets)]
+        #[allow(dead_code)]
+        struct Card {
+            #[widget(select(choices = "summary, player"))]
+            kind: Option<String>,
+            #[widget(reference(table = "authors"))]
+            author: String,
+        }
+        let root = {
+            let mut root = schema_for!(Card);
+            WidgetRegistry::builtin().with::<Card>().apply::<Card>(&mut root);
+            serde_json::to_value(root).unwrap()
+        };
+        assert_eq!(root["properties"]["kind"]["enum"], json!(["summary", "player", null]));
+        assert_eq!(root["properties"]["author"]["widgetOptions"], json!({ "table": "authors" }));
+        assert_eq!(root["properties"]["author"]["pattern"], "^authors:.+$");
+    }
+
+    #[test]
+    #[should_panic(expected = "`Card.author` picks `stars`, which is not a registered widget")]
+    fn picked_widgets_must_be_registered() {
+        #[derive(JsonSchema, Widgets)]
+        #[allow(dead_code)]
+        struct Card {
+            #[widget(stars)]
+            author: String,
+        }
+        let _ = WidgetRegistry::builtin().with::<Card>();
+    }
+
+    #[test]
+    fn conditions_become_visible_if() {
+        let definitions = &schema::<SeoMetadata>()["definitions"];
+        assert_eq!(
+            definitions["OpenGraph"]["properties"]["article"]["visibleIf"],
+            json!({ "field": "og_type", "equals": "Article" })
+        );
+        assert_eq!(
+            definitions["TwitterCard"]["properties"]["player"]["visibleIf"],
+            json!({ "field": "card", "equals": "Player" })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`Card.player` depends on `kind`, which is not a field")]
+    fn conditions_name_a_sibling() {
+        #[derive(JsonSchema, Widgets)]
+        #[allow(dead_code)]
+        struct Card {
+            card: Option<String>,
+            #[visible_if(kind == "player")]
+            player: Option<String>,
+        }
+        let _ = WidgetRegistry::builtin().with::<Card>();
+    }
+
+    #[test]
+    fn macros_dispatch_on_the_widget_name() {
+        let mut tera = tera::Tera::default();
+        tera.add_raw_template(TEMPLATE, &registry().macros()).unwrap();
+        tera.add_raw_template(
+            "field.html",
+            r#"{% import "macros/widgets.html" as widgets %}{{ widgets::control(field=field) }}"#,
+        )
+        .unwrap();
+        let render = |field: Value| {
+            let context = tera::Context::from_value(json!({ "field": field })).unwrap();
+            tera.render("field.html", &context).unwrap()
+        };
+        let field = |widget: &str| {
+            json!({ "path": "bio", "kind": "string", "widget": widget, "value": "Hi", "required": false,
+                    "attrs": {}, "options": [], "widget_options": {}, "error": null })
+        };
+        assert!(render(field("markdown")).contains(r#"<textarea id="field-bio" name="bio" data-kind="string" rows="12" data-widget="markdown">Hi</textarea>"#));
+        assert!(render(field("unknown")).contains(r#"<input type="text" id="field-bio""#));
+    }
+}
@@ -0,0 +1,150 @@
+//! In-process harness: the real router over an in-memory SurrealDB with the
+//! migrations applied and a few fixture records.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use serde_json::Value;
+use tower::ServiceExt;
+
+use website::config::Config;
+use website::db::{self, Db, DbHealth};
+use website::documents;
+use website::migrations::{self, Mode};
+use website::repository::{SurrealCounterRepository, SurrealPostRepository};
+use website::schema::{self, Counter, Post};
+use website::shutdown::Shutdown;
+use website::surql;
+use website::telemetry::REQUEST_ID_HEADER;
+use website::templates::Templates;
+use website::{app, AppState};
+
+/// Record keys of the fixtures loaded by [`TestApp::new`].
+pub struct Fixtures {
+    pub post_id: String,
+    pub counter_id: String,
+}
+
+pub struct TestApp {
+    pub state: Arc<AppState>,
+    pub fixtures: Fixtures,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub content_type: Option<String>,
+    pub request_id: Option<String>,
+    pub location: Option<String>,
+    pub etag: Option<String>,
+    pub body: String,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or_else(|e| panic!("{}: {}", e, self.body))
+    }
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        let source = include_str!("../../config/default.toml").replace("ws://127.0.0.1:8000", "mem://");
+        let config = Config::from_toml(&source).unwrap();
+        let engine = config.database.engine().unwrap();
+        let db: Db = db::connect(&config.database, &engine).await.unwrap();
+        migrations::run(&db, Mode::Apply).await.unwrap();
+        surql::apply(&db, surql::Stale::Keep).await.unwrap();
+
+        let templates = Templates::load(&config.templates.glob()).unwrap();
+        let db = Arc::new(db);
+        let posts = Arc::new(SurrealPostRepository::new(db.clone()));
+        let state = Arc::new(AppState {
+            config: Arc::new(config),
+            templates: Arc::new(templates),
+            documents: Arc::new(documents::registry(&db, posts.clone())),
+            posts,
+            counters: Arc::new(SurrealCounterRepository::new(db.clone())),
+            db,
+            db_health: DbHealth::default(),
+            shutdown: Shutdown::default(),
+            live_reload: None,
+        });
+        let fixtures = load_fixtures(&state).await;
+        Self { state, fixtures }
+    }
+
+    pub fn router(&self) -> Router {
+        app(self.state.clone())
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.send(Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    pub async fn post_json(&self, uri: &str, body: &Value) -> TestResponse {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        self.send(request).await
+    }
+
+    /// Posts `body` as `application/x-www-form-urlencoded`, as a browser
+    /// submits a form.
+    pub async fn post_form(&self, uri: &str, body: &str) -> TestResponse {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        self.send(request).await
+    }
+
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let header = |name| response.headers().get(name).map(|v| v.to_str().unwrap().to_string());
+        let content_type = header(header::CONTENT_TYPE);
+        let request_id = header(REQUEST_ID_HEADER);
+        let location = header(header::LOCATION);
+        let etag = header(header::ETAG);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        TestResponse {
+            status,
+            content_type,
+            request_id,
+            location,
+            etag,
+            body: String::from_utf8(bytes.to_vec()).unwrap(),
+        }
+    }
+
+    /// Serves the router on an ephemeral port, for what `oneshot` cannot
+    /// drive (WebSocket upgrades). The server stops with the test runtime.
+    pub async fn serve(&self) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = self.router();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        addr
+    }
+}
+
+async fn load_fixtures(state: &AppState) -> Fixtures {
+    let post = state
+        .posts
+        .create(Post {
+            id: None,
+            title: "Fixture post".into(),
+            blocks: schema::default_page_schema().into_iter().map(Into::into).collect(),
+        })
+        .await
+        .unwrap();
+    let counter = state.counters.create(Counter { id: None, count: 0 }).await.unwrap();
+
+    Fixtures {
+        post_id: post.id.unwrap().id.to_raw(),
+        counter_id: counter.id.unwrap().id.to_raw(),
+    }
+}
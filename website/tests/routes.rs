@@ -0,0 +1,495 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+use website::telemetry::REQUEST_ID_HEADER;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn api_posts_lists_and_creates() {
+    let app = TestApp::new().await;
+
+    let listed = app.get("/api/posts").await;
+    assert_eq!(listed.status, StatusCode::OK);
+    assert_eq!(listed.json()[0]["title"], "Fixture post");
+
+    let created = app.post_json("/api/posts", &json!({ "title": "Second" })).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    assert_eq!(created.json()["title"], "Second");
+    assert_eq!(app.get("/api/posts").await.json().as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn api_posts_rejects_bad_json() {
+    let app = TestApp::new().await;
+    let response = app.post_json("/api/posts", &json!({ "name": "no title" })).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.json()["code"], "bad_request");
+}
+
+#[tokio::test]
+async fn registered_document_types_get_api_and_admin_routes() {
+    let app = TestApp::new().await;
+
+    let created = app.post_json("/api/pages", &json!({ "metadata": {
+        "title": "About",
+        "published_time": "2025-05-22T08:00:00Z",
+        "images": ["https://example.com/a.png"]
+    } })).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    let key = created.json()["id"]["id"]["String"].as_str().unwrap().to_string();
+    let uri = format!("/api/pages/{}", key);
+
+    let page = app.get(&uri).await.json();
+    assert_eq!(page["metadata"]["published_time"], "2025-05-22T08:00:00Z");
+    let mut page = page;
+    page["metadata"]["title"] = json!("About us");
+    assert_eq!(app.post_json(&uri, &page).await.json()["metadata"]["title"], "About us");
+
+    let list = app.get("/admin/pages/").await;
+    assert!(list.body.contains(&format!("<a href=\"/admin/pages/{}\"", key)), "{}", list.body);
+    assert!(list.body.contains(">About us</td>"));
+    let editor = app.get(&format!("/admin/pages/{}", key)).await;
+    assert_eq!(editor.status, StatusCode::OK);
+    assert!(editor.body.contains(r#"name="metadata.title" data-kind="string" rows="4">About us</textarea>"#), "{}", editor.body);
+    assert!(editor.body.contains(r#"name="metadata.published_time" data-kind="string" value="2025-05-22T08:00""#));
+    assert!(editor.body.contains(r#"name="metadata.images[0]" data-kind="string" required value="https:&#x2F;&#x2F;example.com&#x2F;a.png""#));
+    assert!(editor.body.contains(r#"<option value="SummaryLargeImage">SummaryLargeImage</option>"#));
+    assert_eq!(app.get("/admin/authors/new").await.status, StatusCode::OK);
+
+    let author = app.post_json("/api/authors", &json!({ "name": " " })).await;
+    assert_eq!(author.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(author.json()["details"][0]["path"], "name");
+
+    let delete = Request::delete(&uri).body(Body::empty()).unwrap();
+    assert_eq!(app.send(delete).await.status, StatusCode::OK);
+    assert_eq!(app.get(&uri).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/api/pages").await.json(), json!([]));
+}
+
+#[tokio::test]
+async fn schemas_of_registered_types_are_served_with_etags() {
+    let app = TestApp::new().await;
+
+    let list = app.get("/api/schema").await;
+    assert_eq!(list.status, StatusCode::OK);
+    let names: Vec<Value> = list.json().as_array().unwrap().iter().map(|t| t["name"].clone()).collect();
+    assert_eq!(names, [json!("Post"), json!("Page"), json!("Author")]);
+    assert_eq!(list.json()[1]["schema"], "/api/schema/Page");
+    assert!(list.etag.is_some());
+
+    let page = app.get("/api/schema/Page").await;
+    assert_eq!(page.status, StatusCode::OK);
+    let schema = page.json();
+    assert_eq!(schema["title"], "Page");
+    assert_eq!(schema["definitions"]["SeoMetadata"]["properties"]["title"]["widget"], "textarea");
+    assert!(app.get("/api/schema/Post").await.json()["properties"]["blocks"].is_object());
+
+    let etag = page.etag.unwrap();
+    let revalidate = Request::get("/api/schema/Page").header("if-none-match", &etag).body(Body::empty()).unwrap();
+    let not_modified = app.send(revalidate).await;
+    assert_eq!(not_modified.status, StatusCode::NOT_MODIFIED);
+    assert_eq!((not_modified.etag.as_deref(), not_modified.body.as_str()), (Some(etag.as_str()), ""));
+
+    assert_eq!(app.get("/api/schema/Nope").await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn fields_hidden_by_their_conditions_are_not_saved() {
+    let app = TestApp::new().await;
+
+    let page = json!({ "metadata": { "open_graph": {
+        "og_type": "Website",
+        "article": { "section": "News", "authors": [], "tags": [] },
+        "videos": [{ "url": "https://example.com/a.mp4" }]
+    } } });
+    let created = app.post_json("/api/pages", &page).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let open_graph = &created.json()["metadata"]["open_graph"];
+    assert_eq!((&open_graph["article"], &open_graph["videos"]), (&Value::Null, &json!([])));
+
+    let editor = app.get("/admin/pages/new").await;
+    assert!(editor.body.contains(r#"data-visible-if="metadata.open_graph.og_type" data-equals="Article" hidden disabled"#));
+}
+
+#[tokio::test]
+async fn the_generic_editor_saves_form_posts_and_shows_errors_inline() {
+    let app = TestApp::new().await;
+
+    let body = "metadata.title=About&metadata.images%5B7%5D=https%3A%2F%2Fexample.com%2Fa.png\
+        &metadata.open_graph.og_type=Other&metadata.open_graph.og_type.Other=podcast";
+    let created = app.post_form("/admin/pages/new", body).await;
+    assert_eq!(created.status, StatusCode::SEE_OTHER);
+    let editor_uri = created.location.unwrap();
+    let key = editor_uri.strip_prefix("/admin/pages/").unwrap();
+    let page = app.get(&format!("/api/pages/{}", key)).await.json();
+    assert_eq!(page["metadata"]["images"], json!(["https://example.com/a.png"]));
+    assert_eq!(page["metadata"]["open_graph"]["og_type"], json!({ "Other": "podcast" }));
+
+    let rejected = app.post_form(&editor_uri, "metadata.title=About+us&metadata.robots.max_snippet=lots").await;
+    assert_eq!(rejected.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(rejected.body.contains(r#"name="metadata.title" data-kind="string" rows="4">About us</textarea>"#));
+    assert!(rejected.body.contains(
+        r#"name="metadata.robots.max_snippet" data-kind="integer" aria-invalid="true" aria-describedby="error-metadata.robots.max_snippet""#
+    ));
+    assert!(rejected.body.contains(r#"id="error-metadata.robots.max_snippet">must be a whole number</small>"#));
+    assert_eq!(app.get(&format!("/api/pages/{}", key)).await.json()["metadata"]["title"], "About");
+
+    let boundary = "field-boundary";
+    let multipart = Request::post("/admin/authors/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nAda\r\n--{b}--\r\n",
+            b = boundary
+        )))
+        .unwrap();
+    let author = app.send(multipart).await;
+    assert_eq!(author.status, StatusCode::SEE_OTHER);
+    assert_eq!(app.get("/api/authors").await.json()[0]["name"], "Ada");
+}
+
+#[tokio::test]
+async fn the_post_editor_saves_blocks_as_form_posts() {
+    let app = TestApp::new().await;
+    let uri = format!("/admin/posts/{}", app.fixtures.post_id);
+    let api = format!("/api/posts/{}", app.fixtures.post_id);
+    let header_key = app.get(&api).await.json()["blocks"][0]["id"]["id"]["String"].as_str().unwrap().to_string();
+    let form = |pairs: &[(&str, &str)]| url::form_urlencoded::Serializer::new(String::new()).extend_pairs(pairs).finish();
+
+    let saved = app.post_form(&uri, &form(&[
+        ("title", "Roasting at home"),
+        ("blocks[0].id", &header_key),
+        ("blocks[0].Header.content", "Roasting"),
+        ("blocks[0].Header.element", "h3"),
+        ("blocks[1].Video.name", "Stovetop"),
+        ("blocks[1].Video.description", "Ten minutes."),
+        ("blocks[1].Video.thumbnail_url", "https://example.com/thumb.jpg"),
+        ("blocks[1].Video.upload_date", "2025-05-22T08:00"),
+        ("blocks[1].Video.duration", ""),
+        ("blocks[1].Video.content_url", "https://cdn.example.com/roast.mp4"),
+        ("blocks[1].Video.publisher.name", "Ada"),
+        ("blocks[2].Gallery.images[0].src", "https://example.com/a.png"),
+        ("blocks[2].Gallery.images[0].alt", "Beans"),
+        ("blocks[3].RichText.body", r#"[{ "style": "H2", "children": [{ "text": "Cooling" }] }]"#),
+    ])).await;
+    assert_eq!(saved.status, StatusCode::SEE_OTHER, "{}", saved.body);
+    assert_eq!(saved.location.as_deref(), Some(uri.as_str()));
+    let post = app.get(&api).await.json();
+    assert_eq!(post["title"], "Roasting at home");
+    assert_eq!(post["blocks"][0]["id"]["id"]["String"], header_key.as_str());
+    assert_eq!(post["blocks"][0]["Header"], json!({ "content": "Roasting", "element": "h3" }));
+    let video = &post["blocks"][1]["Video"];
+    assert_eq!((&video["upload_date"], &video["duration"]), (&json!("2025-05-22T08:00:00Z"), &Value::Null));
+    assert_eq!(video["publisher"], json!({ "name": "Ada", "logo": null }));
+    assert_eq!(post["blocks"][2]["Gallery"]["images"][0]["alt"], "Beans");
+    assert_eq!(post["blocks"][3]["RichText"]["body"][0]["style"], "H2");
+    assert_eq!(post["blocks"].as_array().unwrap().len(), 4);
+
+    let rejected = app.post_form(&uri, &form(&[
+        ("title", "Roasting at home"),
+        ("blocks[0].Embed.url", "nope"),
+        ("blocks[0].Embed.title", "Map"),
+        ("blocks[1].RichText.body", "[{"),
+    ])).await;
+    assert_eq!(rejected.status, StatusCode::UNPROCESSABLE_ENTITY);
+    // The post editor comes back, with the errors and the blocks as entered.
+    assert!(rejected.body.contains(&format!(r#"<form is="art-post-form" method="post" action="{}">"#, uri)), "{}", rejected.body);
+    assert!(rejected.body.contains("<li>blocks[0].Embed.url must be a URL</li>"));
+    assert!(rejected.body.contains("<li>blocks[1].RichText.body must be valid JSON</li>"));
+    assert!(rejected.body.contains(r#"id="block-1-url" data-field="url" required value="nope" />"#), "{}", rejected.body);
+    assert!(rejected.body.contains(r#"id="block-1-title" data-field="title" required value="Map" />"#));
+    assert_eq!(app.get(&api).await.json()["blocks"].as_array().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn new_posts_are_written_in_the_post_editor() {
+    let app = TestApp::new().await;
+    let editor = app.get("/admin/posts/new").await;
+    assert_eq!(editor.status, StatusCode::OK);
+    assert!(editor.body.contains(r#"<form is="art-post-form" method="post" action="/admin/posts/new">"#), "{}", editor.body);
+    assert!(!editor.body.contains("post-ws-listener art-uid"));
+
+    let body = "title=Fresh&blocks%5B0%5D.Header.content=Hello&blocks%5B0%5D.Header.element=h1";
+    let created = app.post_form("/admin/posts/new", body).await;
+    assert_eq!(created.status, StatusCode::SEE_OTHER, "{}", created.body);
+    let key = created.location.unwrap().strip_prefix("/admin/posts/").unwrap().to_string();
+    let post = app.get(&format!("/api/posts/{}", key)).await.json();
+    assert_eq!(post["title"], "Fresh");
+    assert_eq!(post["blocks"][0]["Header"], json!({ "content": "Hello", "element": "h1" }));
+}
+
+#[tokio::test]
+async fn writes_that_break_field_rules_are_unprocessable() {
+    let app = TestApp::new().await;
+    let response = app.post_json("/api/posts", &json!({ "title": "" })).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.json()["code"], "validation_failed");
+
+    let post_uri = format!("/api/posts/{}", app.fixtures.post_id);
+    let post = json!({ "title": "Still here", "blocks": [
+        { "Paragraph": { "text": "Fine" } },
+        { "Gallery": { "images": [{ "src": "/a.jpg", "alt": "" }, { "src": " ", "alt": "" }] } },
+        { "Video": {
+            "name": "Clip",
+            "description": "",
+            "thumbnail_url": "thumb.jpg",
+            "upload_date": "1999-01-01T00:00:00Z",
+            "duration": "4 minutes",
+            "content_url": "https://cdn.example.com/clip.mp4",
+        } },
+    ] });
+    let response = app.post_json(&post_uri, &post).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.json()["details"],
+        json!([
+            { "path": "blocks[1].Gallery.images[1].src", "message": "is required" },
+            { "path": "blocks[2].Video.description", "message": "is required" },
+            { "path": "blocks[2].Video.thumbnail_url", "message": "must be an http or https URL" },
+            { "path": "blocks[2].Video.upload_date", "message": "must be on or after 2000-01-01T00:00:00Z" },
+            { "path": "blocks[2].Video.duration", "message": "must match ^P(\\d+D)?(T(\\d+H)?(\\d+M)?(\\d+S)?)?$" },
+        ])
+    );
+    assert_eq!(app.get("/api/posts").await.json()[0]["title"], "Fixture post", "nothing was written");
+
+    let block_uri = format!("{}/blocks", post_uri);
+    let response = app.post_json(&block_uri, &json!({ "Embed": { "url": "https://example.com", "title": "" } })).await;
+    assert_eq!(response.json()["details"][0]["path"], "Embed.title");
+}
+
+#[tokio::test]
+async fn blocks_are_edited_one_at_a_time() {
+    let app = TestApp::new().await;
+    let post_uri = format!("/api/posts/{}", app.fixtures.post_id);
+    let labels = |post: &Value| -> Vec<String> {
+        post["blocks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| {
+                let value = b.get("Header").map(|h| &h["content"]).unwrap_or_else(|| &b["Footer"]["copyright"]);
+                value.as_str().unwrap().to_string()
+            })
+            .collect()
+    };
+    let created = app
+        .post_json(&format!("{}/blocks", post_uri), &json!({ "position": 0, "Header": { "content": "Intro" } }))
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let key = created.json()["id"]["id"]["String"].as_str().unwrap().to_string();
+    let block_uri = format!("{}/blocks/{}", post_uri, key);
+    let post = app.get("/api/posts").await.json()[0].clone();
+    assert_eq!(labels(&post), ["Intro", "Header", "Copyright"]);
+
+    let updated = app.post_json(&block_uri, &json!({ "Header": { "content": "Welcome" } })).await;
+    assert_eq!(updated.status, StatusCode::OK);
+    let moved = app.post_json(&format!("{}/move", block_uri), &json!({ "position": 9 })).await;
+    assert_eq!(labels(&moved.json()), ["Header", "Copyright", "Welcome"]);
+
+    let deleted = app.send(Request::delete(&block_uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    let post = app.get("/api/posts").await.json()[0].clone();
+    assert_eq!(labels(&post), ["Header", "Copyright"]);
+    assert_eq!(app.send(Request::delete(&block_uri).body(Body::empty()).unwrap()).await.status, StatusCode::NOT_FOUND);
+    let missing_post = app.post_json(&format!("/api/posts/nope/blocks/{}/move", key), &json!({ "position": 0 })).await;
+    assert_eq!(missing_post.status, StatusCode::NOT_FOUND);
+    assert_eq!(missing_post.json()["message"], "Post not found");
+
+    // A whole-post save keeps block ids it already has and drops the rest.
+    let mut post = post;
+    post["blocks"].as_array_mut().unwrap().remove(0);
+    let saved = app.post_json(&post_uri, &post).await.json();
+    assert_eq!(labels(&saved), ["Copyright"]);
+    assert_eq!(saved["blocks"][0]["id"], post["blocks"][0]["id"]);
+}
+
+#[tokio::test]
+async fn rich_text_blocks_render_sanitized_html() {
+    let app = TestApp::new().await;
+    let text = json!({ "body": [{
+        "style": "H2",
+        "children": [{ "text": "<b>Hi</b> ", "marks": [] }, { "text": "there", "marks": ["em", "l"] }],
+        "mark_defs": [{ "key": "l", "href": "javascript:alert(1)" }],
+    }] });
+    let uri = format!("/api/posts/{}/blocks", app.fixtures.post_id);
+    let created = app.post_json(&uri, &json!({ "RichText": text })).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    assert_eq!(created.json()["RichText"]["body"][0]["style"], "H2");
+
+    let page = app.get(&format!("/admin/posts/{}", app.fixtures.post_id)).await;
+    assert!(page.body.contains("<h2>&lt;b&gt;Hi&lt;/b&gt; <em>there</em></h2>"), "{}", page.body);
+    assert!(!page.body.contains("href=\"javascript"));
+}
+
+#[tokio::test]
+async fn header_elements_render_and_outline_problems_are_shown() {
+    let app = TestApp::new().await;
+    let uri = format!("/api/posts/{}/blocks", app.fixtures.post_id);
+    for (label, element) in [("Welcome", "h1"), ("Details", "h4"), ("Again", "h1")] {
+        let header = json!({ "Header": {
+            "content": label,
+            "element": element,
+        } });
+        assert_eq!(app.post_json(&uri, &header).await.status, StatusCode::CREATED);
+    }
+
+    let page = app.get(&format!("/admin/posts/{}", app.fixtures.post_id)).await;
+    assert!(page.body.contains("<h1>Welcome</h1>"), "{}", page.body);
+    assert!(page.body.contains("<h4>Details</h4>"));
+    assert!(page.body.contains("<option value=\"h4\" selected>h4</option>"));
+    assert!(page.body.contains("&quot;Details&quot; is an h4 but the heading before it is an h1"));
+    assert!(page.body.contains("2 headings are h1"));
+}
+
+#[tokio::test]
+async fn video_and_code_blocks_render_on_the_server() {
+    let app = TestApp::new().await;
+    let uri = format!("/api/posts/{}/blocks", app.fixtures.post_id);
+    let video = json!({ "Video": {
+        "name": "Roasting </script>",
+        "description": "Stovetop roasting.",
+        "thumbnail_url": "https://example.com/thumb.jpg",
+        "upload_date": "2025-05-22T08:00:00Z",
+        "duration": "PT4M12S",
+        "content_url": "https://cdn.example.com/roast.mp4",
+    } });
+    assert_eq!(app.post_json(&uri, &video).await.status, StatusCode::CREATED);
+    let code = json!({ "Code": { "language": "rust", "code": "let x = \"<y>\";" } });
+    assert_eq!(app.post_json(&uri, &code).await.status, StatusCode::CREATED);
+
+    let page = app.get(&format!("/admin/posts/{}", app.fixtures.post_id)).await;
+    assert!(page.body.contains("<script type=\"application/ld+json\">"), "{}", page.body);
+    assert!(page.body.contains(r#""@type":"VideoObject","name":"Roasting \u003c/script\u003e""#));
+    assert!(page.body.contains(r#""uploadDate":"2025-05-22T08:00:00Z""#));
+    assert!(!page.body.contains("Roasting </script>"), "page data must not close its script");
+    assert!(page.body.contains("<span class=\"hl-keyword\">let</span> x = <span class=\"hl-string\">\"&lt;y&gt;\"</span>"));
+    // The fields' rules become HTML constraints for the browser.
+    assert!(page.body.contains("<input type=\"text\" id=\"block-3-duration\" data-field=\"duration\" data-optional pattern=\"^P"), "{}", page.body);
+    assert!(page.body.contains("data-field=\"upload_date\" min=\"2000-01-01T00:00\" value=\"2025-05-22T08:00\""));
+    // Nested objects get their own fields.
+    assert!(page.body.contains("<input type=\"text\" id=\"block-3-publisher.name\" data-field=\"publisher.name\" value=\"\" />"));
+
+    let preview = app.post_json("/admin/posts/preview", &json!({ "CallToAction": {
+        "heading": "Subscribe", "label": "Go", "href": "javascript:alert(1)",
+    } })).await;
+    assert_eq!(preview.status, StatusCode::OK);
+    assert!(preview.body.contains("<a class=\"cta-button\" href=\"#\">Go</a>"), "{}", preview.body);
+
+    let preview = app.post_json("/admin/posts/preview", &json!({ "Paragraph": { "text": "a <b>\nc" } })).await;
+    assert!(preview.body.contains("<p>a &lt;b&gt;<br>c</p>"), "{}", preview.body);
+    // The editor sends the text of its inputs, which is parsed by field.
+    let preview = app.post_json("/admin/posts/preview", &json!({ "Embed": { "url": "nope", "title": "Map" } })).await;
+    assert_eq!(preview.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn admin_post_page_renders_the_fixture() {
+    let app = TestApp::new().await;
+    let page = app.get(&format!("/admin/posts/{}", app.fixtures.post_id)).await;
+    assert_eq!(page.status, StatusCode::OK);
+    assert!(page.body.contains("<title>Fixture post</title>"));
+    assert!(page.body.contains("id=\"title\" value=\"Fixture post\" placeholder=\"\"  required maxlength=\"200\""), "{}", page.body);
+
+    let missing = app.get("/admin/posts/missing").await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    assert!(missing.content_type.unwrap().starts_with("text/html"));
+}
+
+#[tokio::test]
+async fn counter_page_renders_the_fixture() {
+    let app = TestApp::new().await;
+    let page = app.get("/counter").await;
+    assert_eq!(page.status, StatusCode::OK);
+    assert!(page.body.contains(&format!("/api/counter/{}", app.fixtures.counter_id)));
+}
+
+#[tokio::test]
+async fn counter_actions_save_the_count() {
+    let app = TestApp::new().await;
+    let uri = format!("/api/counter/{}", app.fixtures.counter_id);
+    for action in ["inc", "inc", "dec", "inc"] {
+        let response = app.post_form(&uri, &format!("action={action}")).await;
+        assert_eq!(response.status, StatusCode::SEE_OTHER);
+    }
+    let counter = app.state.counters.get(&app.fixtures.counter_id).await.unwrap().unwrap();
+    assert_eq!(counter.count, 2);
+    assert!(app.get("/counter").await.body.contains(r#"type="number" value="2" />"#));
+
+    assert_eq!(app.post_form(&uri, "action=reset").await.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn responses_carry_a_request_id() {
+    let app = TestApp::new().await;
+    let generated = app.get("/api/posts").await.request_id.expect("an x-request-id header");
+    assert!(!generated.is_empty());
+
+    let request = Request::get("/healthz")
+        .header(REQUEST_ID_HEADER, "from-the-client")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.send(request).await.request_id.as_deref(), Some("from-the-client"));
+}
+
+#[tokio::test]
+async fn rpc_streams_post_updates() {
+    let app = TestApp::new().await;
+    let addr = app.serve().await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/rpc", addr))
+        .await
+        .unwrap();
+    // Let the handler open its live query before the write.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = format!("/api/posts/{}", app.fixtures.post_id);
+    let mut post = app.get("/api/posts").await.json()[0].clone();
+    post["title"] = json!("Renamed");
+    assert_eq!(app.post_json(&uri, &post).await.status, StatusCode::OK);
+
+    let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("a notification for the update")
+        .unwrap()
+        .unwrap();
+    let Message::Text(text) = message else {
+        panic!("expected a text frame, got {:?}", message);
+    };
+    let (action, data): (String, Value) = serde_json::from_str(&text).unwrap();
+    assert_eq!(action, "Update");
+    assert_eq!(data["title"], "Renamed");
+}
+
+#[tokio::test]
+async fn rpc_streams_block_edits_as_whole_posts() {
+    let app = TestApp::new().await;
+    let addr = app.serve().await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/rpc", addr))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let post = app.get("/api/posts").await.json()[0].clone();
+    let key = post["blocks"][0]["id"]["id"]["String"].as_str().unwrap().to_string();
+    let uri = format!("/api/posts/{}/blocks/{}", app.fixtures.post_id, key);
+    let updated = app.post_json(&uri, &json!({ "Header": { "content": "Welcome" } })).await;
+    assert_eq!(updated.status, StatusCode::OK);
+
+    let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("a notification for the block edit")
+        .unwrap()
+        .unwrap();
+    let Message::Text(text) = message else {
+        panic!("expected a text frame, got {:?}", message);
+    };
+    let (action, data): (String, Value) = serde_json::from_str(&text).unwrap();
+    assert_eq!(action, "Update");
+    assert_eq!(data["title"], post["title"]);
+    assert_eq!(data["blocks"][0]["Header"]["content"], "Welcome", "blocks come resolved");
+}